@@ -1,5 +1,6 @@
 use crate::{
-    expression::format_expression, format_comment, format_whitespace, PrintInfo, Rule, SpaceType,
+    expression::format_expression, format_comment, format_whitespace, push_comment_break,
+    PrintInfo, Rule, SpaceType,
 };
 use pad::PadStr;
 use pest::iterators::{Pair, Pairs};
@@ -78,7 +79,9 @@ pub fn format_assign(assign: Pair<Rule>) -> Vec<PrintInfo> {
                 SpaceType::None,
             )),
             Rule::assign_lines => print_list.append(&mut format_assign_lines(iner)),
-            Rule::WHITESPACE => {}
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            //a comment ending the line keeps the line break after it
+            Rule::WHITESPACE => push_comment_break(&mut print_list, iner),
 
             une => panic!(" unexpected assign {:?}", une),
         }
@@ -166,11 +169,9 @@ fn get_to_variable(iter: &mut Pairs<Rule>) -> (Vec<PrintInfo>, String) {
     let variable = loop {
         let next = iter.next().expect("invalid assign");
         match next.as_rule() {
-            Rule::COMMENT => {
-                before_var.push(PrintInfo::new(next.as_str().to_string(), SpaceType::None))
-            }
+            Rule::COMMENT => before_var.push(PrintInfo::new(format_comment(next), SpaceType::None)),
             Rule::variable => break next.as_str().to_string(),
-            Rule::WHITESPACE => {}
+            Rule::WHITESPACE => push_comment_break(&mut before_var, next),
             une => panic!(" unexpected assign {:?}", une),
         }
     };
@@ -183,7 +184,7 @@ fn get_to_assign_expression(iter: &mut Pairs<Rule>) -> (Vec<PrintInfo>, Vec<Prin
         let next = iter.next().expect("invalid assign");
         match next.as_rule() {
             Rule::COMMENT => {
-                before_expr.push(PrintInfo::new(next.as_str().to_string(), SpaceType::None))
+                before_expr.push(PrintInfo::new(format_comment(next), SpaceType::None))
             }
             Rule::expression => {
                 break format_expression(next, true);
@@ -191,7 +192,7 @@ fn get_to_assign_expression(iter: &mut Pairs<Rule>) -> (Vec<PrintInfo>, Vec<Prin
             Rule::equals => {
                 before_expr.push(PrintInfo::new(next.as_str().to_string(), SpaceType::None))
             }
-            Rule::WHITESPACE => {}
+            Rule::WHITESPACE => push_comment_break(&mut before_expr, next),
             une => panic!(" unexpected assign {:?}", une),
         }
     };
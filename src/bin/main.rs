@@ -1,6 +1,7 @@
 extern crate clap;
 use abl_formatter::*;
 use clap::{App, Arg};
+use encoding_rs::Encoding;
 fn main() {
     let options = App::new("abl_formatter")
     .version("0.1")
@@ -9,11 +10,212 @@ fn main() {
         Arg::with_name("FILE")
         .index(1)
         .help("The file and (Accompanying path if neccessary ) to process standard input if empty or -- Standard input is used")
+    )
+    .arg(
+        Arg::with_name("procedure-blank-lines")
+        .long("procedure-blank-lines")
+        .takes_value(true)
+        .default_value("1")
+        .help("The number of blank lines kept before and after each procedure, function and method")
+    )
+    .arg(
+        Arg::with_name("procedure-end-comment")
+        .long("procedure-end-comment")
+        .help("Names the procedure or function in a comment after its END")
+    )
+    .arg(
+        Arg::with_name("sort-using")
+        .long("sort-using")
+        .help("Sorts each block of USING statements and removes repeated ones")
+    )
+    .arg(
+        Arg::with_name("then-placement")
+        .long("then-placement")
+        .takes_value(true)
+        .possible_values(&["keep", "same-line", "next-line"])
+        .default_value("keep")
+        .help("Whether THEN stays on the line of the IF condition or starts the next line")
+    )
+    .arg(
+        Arg::with_name("else-if-placement")
+        .long("else-if-placement")
+        .takes_value(true)
+        .possible_values(&["keep", "same-line", "next-line"])
+        .default_value("keep")
+        .help("Whether ELSE IF chains are flattened onto one line or the IF is nested on the next line")
+    )
+    .arg(
+        Arg::with_name("if-branch-placement")
+        .long("if-branch-placement")
+        .takes_value(true)
+        .possible_values(&["keep", "same-line", "next-line"])
+        .default_value("keep")
+        .help("Whether a single statement after THEN or ELSE stays on that line or is indented on the next line")
+    )
+    .arg(
+        Arg::with_name("split-conditions")
+        .long("split-conditions")
+        .help("Puts each AND/OR condition of a WHERE or IF on its own line with the operators aligned")
+    )
+    .arg(
+        Arg::with_name("line-width")
+        .long("line-width")
+        .takes_value(true)
+        .default_value("120")
+        .help("The width past which DISPLAY, MESSAGE and UPDATE put their items one per line")
+    )
+    .arg(
+        Arg::with_name("one-item-per-line")
+        .long("one-item-per-line")
+        .help("Always puts the items of DISPLAY, MESSAGE and UPDATE one per line")
+    )
+    .arg(
+        Arg::with_name("split-event-lists")
+        .long("split-event-lists")
+        .help("Puts each event of an ON or WAIT-FOR event list on its own line")
+    )
+    .arg(
+        Arg::with_name("label-placement")
+        .long("label-placement")
+        .takes_value(true)
+        .possible_values(&["keep", "same-line", "next-line"])
+        .default_value("next-line")
+        .help("Whether a block label stays on the line of its block or goes on a line of its own")
+    )
+    .arg(
+        Arg::with_name("label-end-comment")
+        .long("label-end-comment")
+        .help("Names the label in a comment after the END of a labelled block")
+    )
+    .arg(
+        Arg::with_name("include-args-per-line")
+        .long("include-args-per-line")
+        .help("Puts each argument of an include on its own line aligned after the file name")
+    )
+    .arg(
+        Arg::with_name("fragment")
+        .long("fragment")
+        .help("Formats an include file that is a field list, phrase or part of a statement, on by default for .i files")
+    )
+    .arg(
+        Arg::with_name("base-indent")
+        .long("base-indent")
+        .takes_value(true)
+        .default_value("0")
+        .help("The nesting level the file is included at")
+    )
+    .arg(
+        Arg::with_name("encoding")
+        .long("encoding")
+        .takes_value(true)
+        .default_value("auto")
+        .help("The encoding of the source such as utf-8 or windows-1252, auto uses a byte order mark or UTF-8 and falls back to windows-1252")
+    )
+    .arg(
+        Arg::with_name("end-of-line")
+        .long("end-of-line")
+        .takes_value(true)
+        .possible_values(&["auto", "lf", "crlf"])
+        .default_value("auto")
+        .help("The line ending written, auto uses whichever ending most lines of the source have")
+    )
+    .arg(
+        Arg::with_name("max-blank-lines")
+        .long("max-blank-lines")
+        .takes_value(true)
+        .default_value("2")
+        .help("The most blank lines kept in a row")
+    )
+    .arg(
+        Arg::with_name("define-blank-lines")
+        .long("define-blank-lines")
+        .takes_value(true)
+        .help("The number of blank lines between a run of DEFINE statements and the code after it")
+    )
+    .arg(
+        Arg::with_name("block-edge-blank-lines")
+        .long("block-edge-blank-lines")
+        .takes_value(true)
+        .help("The number of blank lines at the start and end of a block")
+    )
+    .arg(
+        Arg::with_name("align-trailing-comments")
+        .long("align-trailing-comments")
+        .help("Lines up the comments ending consecutive lines of code in one column")
+    )
+    .arg(
+        Arg::with_name("trailing-comment-gap")
+        .long("trailing-comment-gap")
+        .takes_value(true)
+        .default_value("1")
+        .help("The least number of spaces between code and the comment after it")
     )   .get_matches();
     let input_type = match options.value_of("FILE") {
         Some(s) if s != "-" && s != "--" => IoType::FromFile(s.to_owned()),
         _ => IoType::FromStdIn,
     };
 
-    format_code(input_type);
+    let format_options = FormatOptions {
+        procedure_blank_lines: options
+            .value_of("procedure-blank-lines")
+            .and_then(|x| x.parse().ok())
+            .expect("procedure-blank-lines must be a number"),
+        procedure_end_comment: options.is_present("procedure-end-comment"),
+        sort_using: options.is_present("sort-using"),
+        then_placement: line_placement(options.value_of("then-placement")),
+        else_if_placement: line_placement(options.value_of("else-if-placement")),
+        if_branch_placement: line_placement(options.value_of("if-branch-placement")),
+        split_conditions: options.is_present("split-conditions"),
+        line_width: options
+            .value_of("line-width")
+            .and_then(|x| x.parse().ok())
+            .expect("line-width must be a number"),
+        one_item_per_line: options.is_present("one-item-per-line"),
+        split_event_lists: options.is_present("split-event-lists"),
+        label_placement: line_placement(options.value_of("label-placement")),
+        label_end_comment: options.is_present("label-end-comment"),
+        include_args_per_line: options.is_present("include-args-per-line"),
+        fragment: options.is_present("fragment")
+            || matches!(&input_type, IoType::FromFile(file) if file.to_lowercase().ends_with(".i")),
+        base_indent: options
+            .value_of("base-indent")
+            .and_then(|x| x.parse().ok())
+            .expect("base-indent must be a number"),
+        encoding: match options.value_of("encoding") {
+            Some("auto") | None => None,
+            Some(label) => {
+                Some(Encoding::for_label(label.as_bytes()).expect("encoding is not a known encoding"))
+            }
+        },
+        end_of_line: match options.value_of("end-of-line") {
+            Some("lf") => EndOfLine::Lf,
+            Some("crlf") => EndOfLine::Crlf,
+            _ => EndOfLine::Auto,
+        },
+        max_blank_lines: options
+            .value_of("max-blank-lines")
+            .and_then(|x| x.parse().ok())
+            .expect("max-blank-lines must be a number"),
+        define_blank_lines: options
+            .value_of("define-blank-lines")
+            .map(|x| x.parse().expect("define-blank-lines must be a number")),
+        block_edge_blank_lines: options
+            .value_of("block-edge-blank-lines")
+            .map(|x| x.parse().expect("block-edge-blank-lines must be a number")),
+        align_trailing_comments: options.is_present("align-trailing-comments"),
+        trailing_comment_gap: options
+            .value_of("trailing-comment-gap")
+            .and_then(|x| x.parse().ok())
+            .expect("trailing-comment-gap must be a number"),
+    };
+
+    format_code(input_type, &format_options);
+}
+
+fn line_placement(value: Option<&str>) -> LinePlacement {
+    match value {
+        Some("same-line") => LinePlacement::SameLine,
+        Some("next-line") => LinePlacement::NextLine,
+        _ => LinePlacement::Keep,
+    }
 }
@@ -0,0 +1,105 @@
+use crate::{
+    expression::format_expression, format_comment, format_words, FormatOptions, PrintInfo, Rule,
+    SpaceType, TAB_WIDTH,
+};
+use pest::iterators::Pair;
+
+/**
+    DO or REPEAT with its options, the ON phrases each start a line
+    when the header does not fit within the line width
+*/
+pub fn format_block_header(
+    block_header: Pair<Rule>,
+    indent: usize,
+    options: &FormatOptions,
+) -> Vec<PrintInfo> {
+    let mut words = Vec::new();
+    let mut on_phrases = Vec::new();
+
+    for iner in block_header.into_inner() {
+        match iner.as_rule() {
+            Rule::block_keyword => words.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::block_option => {
+                let option = iner.into_inner().next().expect("empty block option");
+                match option.as_rule() {
+                    Rule::block_range => words.append(&mut format_block_range(option)),
+                    Rule::block_while => words.append(&mut format_block_while(option)),
+                    Rule::on_error_phrase => {
+                        on_phrases.push(words.len());
+                        words.append(&mut format_words(option));
+                    }
+                    Rule::comma => words.push(PrintInfo::new(
+                        option.as_str().to_string(),
+                        SpaceType::NoLeftPad,
+                    )),
+                    Rule::keyword => words.push(PrintInfo::new(
+                        option.as_str().to_uppercase(),
+                        SpaceType::None,
+                    )),
+                    Rule::variable => {
+                        words.push(PrintInfo::new(option.as_str().to_string(), SpaceType::None))
+                    }
+                    _ => words.append(&mut format_words(option)),
+                }
+            }
+            Rule::COMMENT => words.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected block header {:?}", une),
+        }
+    }
+
+    //each word is followed by a space or the colon
+    let width = indent * TAB_WIDTH + words.iter().map(|x| x.line.trim().len() + 1).sum::<usize>();
+    if width <= options.line_width {
+        return words;
+    }
+    for start in on_phrases.into_iter().rev() {
+        words.insert(start, PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+    }
+    words
+}
+
+/**
+    the counter with single spaces around =, TO and BY
+*/
+fn format_block_range(block_range: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in block_range.into_inner() {
+        match iner.as_rule() {
+            Rule::variable => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::equals => print_list.push(PrintInfo::new("=".to_string(), SpaceType::None)),
+            Rule::to_keyword | Rule::by_keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::expression => print_list.append(&mut format_expression(iner, false)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected block range {:?}", une),
+        }
+    }
+    print_list
+}
+
+fn format_block_while(block_while: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in block_while.into_inner() {
+        match iner.as_rule() {
+            Rule::while_keyword => {
+                print_list.push(PrintInfo::new("WHILE".to_string(), SpaceType::None))
+            }
+            Rule::expression => print_list.append(&mut format_expression(iner, true)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected while {:?}", une),
+        }
+    }
+    print_list
+}
@@ -0,0 +1,67 @@
+use crate::{
+    format_comment, format_words, function::format_function_parameters, PrintInfo, Rule, SpaceType,
+};
+use pest::iterators::Pair;
+
+/**
+    the CLASS or INTERFACE line, returns the keyword its END repeats
+*/
+pub fn format_class_header(header: Pair<Rule>) -> (Vec<PrintInfo>, String) {
+    let keyword = first_keyword(&header);
+    (format_words(header), keyword)
+}
+
+/**
+    the METHOD, CONSTRUCTOR or DESTRUCTOR line, the parameter list is kept
+    against the name, returns the keyword its END repeats
+*/
+pub fn format_method_header(header: Pair<Rule>, wrap: bool) -> (Vec<PrintInfo>, String) {
+    let keyword = first_keyword(&header);
+    let mut print_list = Vec::new();
+
+    for iner in header.into_inner() {
+        match iner.as_rule() {
+            Rule::function_parameters => {
+                let mut parameters = format_function_parameters(iner, wrap);
+                if let Some(first) = parameters.first_mut() {
+                    first.spacing_attribute = SpaceType::NoLeftPad;
+                }
+                print_list.append(&mut parameters);
+            }
+            Rule::define_name | Rule::type_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::temp_table_extent => print_list.append(&mut format_words(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            _ => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+        }
+    }
+    (print_list, keyword)
+}
+
+/**
+    a GET or SET accessor of a property, returns the keyword its END repeats
+*/
+pub fn format_property_accessor(accessor: Pair<Rule>) -> (Vec<PrintInfo>, String) {
+    let keyword = accessor
+        .clone()
+        .into_inner()
+        .find(|x| x.as_rule() == Rule::accessor_keyword)
+        .map(|x| x.as_str().to_uppercase())
+        .unwrap_or_default();
+    let (print_list, _) = format_method_header(accessor, false);
+    (print_list, keyword)
+}
+
+fn first_keyword(header: &Pair<Rule>) -> String {
+    header
+        .clone()
+        .into_inner()
+        .next()
+        .map(|x| x.as_str().to_uppercase())
+        .unwrap_or_default()
+}
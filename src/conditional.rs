@@ -0,0 +1,239 @@
+use crate::{
+    expression::{format_datatype, format_expression},
+    format_comment, format_whitespace, is_glued, FormatOptions, LinePlacement, PrintInfo, Rule,
+    SpaceType,
+};
+use pest::iterators::Pair;
+
+/**
+    IF and its condition up to THEN, where THEN goes depends on the options
+*/
+pub fn format_if_then(if_then: Pair<Rule>, options: &FormatOptions) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in if_then.into_inner() {
+        match iner.as_rule() {
+            Rule::if_keyword => {}
+            Rule::if_condition => {
+                let condition = format_condition(iner);
+                match split_condition("IF", &condition).filter(|_| options.split_conditions) {
+                    Some(lines) => {
+                        for (i, line) in lines.into_iter().enumerate() {
+                            if i > 0 {
+                                print_list
+                                    .push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                                print_list.push(PrintInfo::new(line, SpaceType::Padded));
+                            } else {
+                                //the AND/OR lines are indented below, so IF is not padded to line up with them
+                                let first = format!("IF {}", line["IF".len()..].trim_start());
+                                print_list.push(PrintInfo::new(first, SpaceType::Padded));
+                            }
+                        }
+                    }
+                    None => {
+                        print_list.push(PrintInfo::new("IF".to_string(), SpaceType::None));
+                        print_list.extend(condition);
+                    }
+                }
+            }
+            Rule::then_keyword => {
+                match options.then_placement {
+                    LinePlacement::Keep => {}
+                    LinePlacement::SameLine => {
+                        while print_list
+                            .last()
+                            .is_some_and(|x| x.spacing_attribute == SpaceType::NewLine)
+                        {
+                            print_list.pop();
+                        }
+                    }
+                    LinePlacement::NextLine => {
+                        if !print_list
+                            .last()
+                            .is_some_and(|x| x.spacing_attribute == SpaceType::NewLine)
+                        {
+                            print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                        }
+                    }
+                }
+                print_list.push(PrintInfo::new("THEN".to_string(), SpaceType::None));
+            }
+            Rule::WHITESPACE => {
+                if let Some(f) = format_whitespace(iner) {
+                    print_list.push(f);
+                }
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            une => panic!("unexpected if {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    the CASE line up to its colon
+*/
+pub fn format_case_header(case_header: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = vec![PrintInfo::new("CASE".to_string(), SpaceType::None)];
+    for iner in case_header.into_inner() {
+        match iner.as_rule() {
+            Rule::case_keyword | Rule::WHITESPACE => {}
+            Rule::expression => print_list.append(&mut format_expression(iner, false)),
+            Rule::keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::datatype => print_list.append(&mut format_datatype(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            une => panic!("unexpected case {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    a WHEN and its values up to THEN
+*/
+pub fn format_when_then(when_then: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    for iner in when_then.into_inner() {
+        match iner.as_rule() {
+            Rule::when_keyword | Rule::then_keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::if_condition => print_list.append(&mut format_condition(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected when {:?}", une),
+        }
+    }
+    print_list
+}
+
+fn format_condition(condition: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in condition.into_inner() {
+        match iner.as_rule() {
+            Rule::expression => print_list.append(&mut format_expression(iner, true)),
+            Rule::keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::comma => print_list.push(PrintInfo::new(
+                iner.as_str().to_string(),
+                SpaceType::NoLeftPad,
+            )),
+            Rule::datatype => print_list.append(&mut format_datatype(iner)),
+            Rule::WHITESPACE => {
+                if let Some(f) = format_whitespace(iner) {
+                    print_list.push(f);
+                }
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            une => panic!("unexpected if condition {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    lays a condition out with each AND/OR starting a line, the operators are right aligned
+    in a column under the keyword so the conditions line up after it, the contents of
+    brackets are split the same way aligned after the bracket,
+    returns None when there is no AND/OR to split on
+*/
+pub fn split_condition(keyword: &str, condition: &[PrintInfo]) -> Option<Vec<String>> {
+    let words = condition
+        .iter()
+        .filter(|x| {
+            x.spacing_attribute != SpaceType::NewLine && x.spacing_attribute != SpaceType::LineBreak
+        })
+        .collect::<Vec<_>>();
+
+    //only brackets grouping conditions are split inside, not the arguments of a function
+    let mut groups = Vec::new();
+    let mut top_level_width = 0;
+    let mut splits = false;
+    for (i, word) in words.iter().enumerate() {
+        match word.line.trim() {
+            "(" => groups.push(is_group(i.checked_sub(1).map(|x| words[x]))),
+            ")" => {
+                groups.pop();
+            }
+            op if is_logical(op) && groups.iter().all(|x| *x) => {
+                splits = true;
+                if groups.is_empty() {
+                    top_level_width = top_level_width.max(op.len());
+                }
+            }
+            _ => {}
+        }
+    }
+    if !splits {
+        return None;
+    }
+
+    let width = keyword.len().max(top_level_width);
+    let mut lines = Vec::new();
+    let mut line = format!("{:<width$}", keyword, width = width);
+    let mut prev = Some(&SpaceType::None);
+    let mut prev_word = None;
+    //the column each open bracket's conditions start at, None inside a function call
+    let mut columns = vec![Some(width + 1)];
+    for word in words {
+        let text = word.line.trim();
+        match text {
+            op if is_logical(op) && columns.iter().all(|x| x.is_some()) => {
+                let column = columns.last().copied().flatten().unwrap_or(width + 1);
+                lines.push(line);
+                line = format!("{:>width$}", op, width = column.saturating_sub(1));
+                prev = Some(&SpaceType::None);
+                prev_word = Some(word);
+                continue;
+            }
+            _ => {}
+        }
+        if !is_glued(prev, &word.spacing_attribute) {
+            line.push(' ');
+        }
+        line.push_str(text);
+        match text {
+            "(" if is_group(prev_word) => columns.push(Some(line.len())),
+            "(" => columns.push(None),
+            ")" if columns.len() > 1 => {
+                columns.pop();
+            }
+            _ => {}
+        }
+        prev = Some(&word.spacing_attribute);
+        prev_word = Some(word);
+    }
+    lines.push(line);
+    Some(lines)
+}
+
+fn is_logical(word: &str) -> bool {
+    word.eq_ignore_ascii_case("AND") || word.eq_ignore_ascii_case("OR")
+}
+
+/**
+    whether an opening bracket groups conditions, it holds the arguments
+    of a call when it follows a function or method name
+*/
+fn is_group(previous: Option<&PrintInfo>) -> bool {
+    previous.is_none_or(|word| {
+        let word = word.line.trim();
+        is_operator(word) || !word.ends_with(|x: char| x.is_alphanumeric() || x == '-' || x == '_')
+    })
+}
+
+fn is_operator(word: &str) -> bool {
+    [
+        "AND", "OR", "NOT", "EQ", "NE", "GT", "GE", "LT", "LE", "BEGINS", "MATCHES", "CONTAINS",
+        "MODULO",
+    ]
+    .iter()
+    .any(|x| word.eq_ignore_ascii_case(x))
+}
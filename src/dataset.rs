@@ -0,0 +1,220 @@
+use crate::{
+    align_columns, format_comment, format_words, print_list_to_line, push_word, PrintInfo, Rule,
+    SpaceType,
+};
+use pad::PadStr;
+use pest::iterators::Pair;
+
+pub fn format_define_dataset(dataset: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    let mut relations = Vec::new();
+
+    for iner in dataset.into_inner() {
+        match iner.as_rule() {
+            Rule::define_keyword
+            | Rule::define_modifier
+            | Rule::dataset_keyword
+            | Rule::for_keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::define_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::temp_table_option => print_list.append(&mut format_words(iner)),
+            Rule::dataset_buffers => {
+                let mut buffers: Vec<String> = Vec::new();
+                for part in iner.into_inner() {
+                    match part.as_rule() {
+                        Rule::record_name => buffers.push(part.as_str().to_string()),
+                        //a comment stays with the buffer before it
+                        Rule::COMMENT => {
+                            if let Some(buffer) = buffers.last_mut() {
+                                push_word(buffer, &format_comment(part));
+                            }
+                        }
+                        Rule::comma | Rule::WHITESPACE => {}
+                        une => panic!("unexpected dataset buffers {:?}", une),
+                    }
+                }
+                print_list.append(&mut format_buffer_list(buffers));
+            }
+            Rule::data_relation => relations.push(iner),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected dataset {:?}", une),
+        }
+    }
+
+    print_list.append(&mut format_data_relations(relations));
+    print_list
+}
+
+pub fn format_define_data_source(data_source: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    let mut buffers = Vec::new();
+
+    for iner in data_source.into_inner() {
+        match iner.as_rule() {
+            Rule::define_keyword
+            | Rule::define_modifier
+            | Rule::data_source_keyword
+            | Rule::for_keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::define_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::data_source_query => print_list.append(&mut format_words(iner)),
+            //commas between the buffers are added back when the buffers are listed
+            Rule::comma if !buffers.is_empty() => {}
+            Rule::comma => print_list.push(PrintInfo::new(
+                iner.as_str().to_string(),
+                SpaceType::NoLeftPad,
+            )),
+            Rule::data_source_buffer => {
+                let mut columns = vec![String::new(); 2];
+                for part in iner.into_inner() {
+                    match part.as_rule() {
+                        Rule::record_name => columns[0] = part.as_str().to_string(),
+                        Rule::data_source_keys => {
+                            columns[1] = print_list_to_line(format_words(part))
+                        }
+                        //a comment stays with the buffer name or keys before it
+                        Rule::COMMENT => {
+                            let column = if columns[1].is_empty() { 0 } else { 1 };
+                            push_word(&mut columns[column], &format_comment(part));
+                        }
+                        Rule::WHITESPACE => {}
+                        une => panic!("unexpected data-source buffer {:?}", une),
+                    }
+                }
+                buffers.push(columns);
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected data-source {:?}", une),
+        }
+    }
+
+    print_list.append(&mut format_buffer_list(align_columns(&buffers)));
+    print_list
+}
+
+/**
+    a single buffer stays on the FOR line, several are put one per line
+*/
+pub fn format_buffer_list(buffers: Vec<String>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    let last = buffers.len().saturating_sub(1);
+    let one_per_line = buffers.len() > 1;
+
+    for (i, buffer) in buffers.into_iter().enumerate() {
+        if one_per_line {
+            print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+        }
+        print_list.push(PrintInfo::new(buffer, SpaceType::None));
+        if i != last {
+            print_list.push(PrintInfo::new(",".to_string(), SpaceType::NoLeftPad));
+        }
+    }
+    print_list
+}
+
+/**
+    each relation goes on its own line with the relation names, buffers and
+    RELATION-FIELDS pairs aligned against the other relations
+*/
+fn format_data_relations(relations: Vec<Pair<Rule>>) -> Vec<PrintInfo> {
+    let mut rows = Vec::with_capacity(relations.len());
+    let mut relation_pairs = Vec::with_capacity(relations.len());
+
+    for relation in relations {
+        let mut columns = vec![String::new(); 7];
+        let mut pairs = Vec::new();
+        let mut flags = Vec::new();
+        let mut buffer_count = 0;
+        for iner in relation.into_inner() {
+            match iner.as_rule() {
+                Rule::data_relation_keyword => columns[0] = iner.as_str().to_uppercase(),
+                Rule::define_name => columns[1] = iner.as_str().to_string(),
+                Rule::for_keyword => columns[2] = iner.as_str().to_uppercase(),
+                Rule::record_name if buffer_count == 0 => {
+                    buffer_count += 1;
+                    columns[3] = format!("{},", iner.as_str())
+                }
+                Rule::record_name => columns[4] = iner.as_str().to_string(),
+                Rule::relation_fields => {
+                    let mut names: Vec<String> = Vec::new();
+                    for field in iner.into_inner() {
+                        match field.as_rule() {
+                            Rule::define_name => names.push(field.as_str().to_string()),
+                            //a comment stays with the field before it
+                            Rule::COMMENT => match names.last_mut() {
+                                Some(name) => push_word(name, &format_comment(field)),
+                                None => flags
+                                    .push(PrintInfo::new(format_comment(field), SpaceType::None)),
+                            },
+                            Rule::relation_fields_keyword
+                            | Rule::left_parenthesis
+                            | Rule::right_parenthesis
+                            | Rule::comma
+                            | Rule::WHITESPACE => {}
+                            une => panic!("unexpected relation-fields {:?}", une),
+                        }
+                    }
+                    pairs = names.chunks(2).map(|pair| pair.join(", ")).collect();
+                }
+                Rule::dataset_flag => flags.push(PrintInfo::new(
+                    iner.as_str().to_uppercase(),
+                    SpaceType::None,
+                )),
+                Rule::COMMENT => flags.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+                Rule::comma | Rule::WHITESPACE => {}
+                une => panic!("unexpected data-relation {:?}", une),
+            }
+        }
+        columns[6] = print_list_to_line(flags);
+        rows.push(columns);
+        relation_pairs.push(pairs);
+    }
+
+    //the nth pair of every relation is padded to the same width so the pairs line up
+    let mut pair_widths: Vec<usize> = Vec::new();
+    for pairs in relation_pairs.iter() {
+        for (i, pair) in pairs.iter().enumerate() {
+            match pair_widths.get_mut(i) {
+                Some(width) if pair.len() > *width => *width = pair.len(),
+                Some(_) => {}
+                None => pair_widths.push(pair.len()),
+            }
+        }
+    }
+    for (columns, pairs) in rows.iter_mut().zip(relation_pairs) {
+        if pairs.is_empty() {
+            continue;
+        }
+        let last = pairs.len() - 1;
+        let mut relation_fields = String::from("RELATION-FIELDS (");
+        for (i, pair) in pairs.into_iter().enumerate() {
+            if i == last {
+                relation_fields.push_str(&pair);
+            } else {
+                relation_fields.push_str(&format!("{},", pair).pad_to_width(pair_widths[i] + 2));
+            }
+        }
+        relation_fields.push(')');
+        columns[5] = relation_fields;
+    }
+
+    align_columns(&rows)
+        .into_iter()
+        .flat_map(|line| {
+            vec![
+                PrintInfo::new("\n".to_string(), SpaceType::NewLine),
+                PrintInfo::new(line, SpaceType::None),
+            ]
+        })
+        .collect()
+}
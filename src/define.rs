@@ -0,0 +1,245 @@
+use crate::{
+    align_columns, expression::format_datatype, format_comment, function::format_function,
+    print_list_to_line, push_word, PrintInfo, Rule, SpaceType, COMMENT_MARK,
+};
+use pest::iterators::Pair;
+
+/**
+    the columns of a single define statement, every column is aligned
+    against the same column of the other defines in the run
+*/
+struct DefineColumns {
+    columns: [String; 6],
+    trailing_comment: Option<String>,
+    parameter: bool,
+}
+
+enum DefineLine {
+    Define(DefineColumns),
+    Comment(String),
+    Blank,
+}
+
+const PREFIX: usize = 0;
+const NAME: usize = 1;
+const TYPE: usize = 2;
+const INITIAL: usize = 3;
+const OPTIONS: usize = 4;
+const NO_UNDO: usize = 5;
+
+/**
+    formats a run of consecutive DEFINE VARIABLE/PARAMETER/BUFFER statements,
+    returns the lines without indentation so the caller can indent them,
+    empty lines are blank lines, in a procedure its parameters are kept apart
+    from the defines after them by a blank line
+*/
+pub fn format_define_variables(defines: Pair<Rule>, in_procedure: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut newlines_since_define = 0;
+    let mut parameters_end = None;
+
+    for iner in defines.into_inner() {
+        match iner.as_rule() {
+            Rule::define_variable | Rule::define_buffer => {
+                push_blank_lines(&mut lines, newlines_since_define);
+                let define = format_define_variable(iner);
+                if define.parameter {
+                    parameters_end = Some(lines.len() + 1);
+                } else if let Some(end) = parameters_end.take().filter(|_| in_procedure) {
+                    //comments before the first local go with it
+                    if !lines[end..]
+                        .iter()
+                        .any(|line| matches!(line, DefineLine::Blank))
+                    {
+                        lines.insert(end, DefineLine::Blank);
+                    }
+                }
+                lines.push(DefineLine::Define(define));
+                newlines_since_define = 0;
+            }
+            Rule::COMMENT => {
+                let comment = format_comment(iner);
+                match lines.last_mut() {
+                    Some(DefineLine::Define(define))
+                        if newlines_since_define == 0 && define.trailing_comment.is_none() =>
+                    {
+                        define.trailing_comment = Some(comment);
+                    }
+                    _ => {
+                        push_blank_lines(&mut lines, newlines_since_define);
+                        lines.push(DefineLine::Comment(comment));
+                        newlines_since_define = 0;
+                    }
+                }
+            }
+            Rule::WHITESPACE => {
+                if iner.as_str().contains('\n') {
+                    newlines_since_define += 1;
+                }
+            }
+            une => panic!("unexpected define {:?}", une),
+        }
+    }
+
+    //newlines consumed after the last define still have to be printed
+    for _ in 0..newlines_since_define {
+        lines.push(DefineLine::Blank);
+    }
+
+    //blank lines split the run into groups that are aligned independently
+    let mut aligned_lines = Vec::with_capacity(lines.len());
+    let mut group = Vec::new();
+    for line in lines {
+        match line {
+            DefineLine::Blank => {
+                aligned_lines.append(&mut align_define_group(group));
+                aligned_lines.push(String::new());
+                group = Vec::new();
+            }
+            line => group.push(line),
+        }
+    }
+    aligned_lines.append(&mut align_define_group(group));
+    aligned_lines
+}
+
+fn align_define_group(group: Vec<DefineLine>) -> Vec<String> {
+    let rows = group
+        .iter()
+        .filter_map(|line| match line {
+            DefineLine::Define(define) => Some(define.columns.to_vec()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut aligned_rows = align_columns(&rows).into_iter();
+
+    group
+        .into_iter()
+        .map(|line| match line {
+            DefineLine::Define(define) => {
+                let mut aligned = aligned_rows.next().unwrap_or_default();
+                aligned.push('.');
+                if let Some(comment) = define.trailing_comment {
+                    aligned.push(COMMENT_MARK);
+                    aligned.push_str(&comment);
+                }
+                aligned
+            }
+            DefineLine::Comment(comment) => comment,
+            DefineLine::Blank => String::new(),
+        })
+        .collect()
+}
+
+fn push_blank_lines(lines: &mut Vec<DefineLine>, newlines: usize) {
+    if lines.is_empty() {
+        return;
+    }
+    for _ in 1..newlines {
+        lines.push(DefineLine::Blank);
+    }
+}
+
+fn format_define_variable(define: Pair<Rule>) -> DefineColumns {
+    let mut columns: [String; 6] = Default::default();
+    let mut options = Vec::new();
+    let mut parameter = false;
+
+    for iner in define.into_inner() {
+        match iner.as_rule() {
+            Rule::parameter_keyword => {
+                parameter = true;
+                push_word(&mut columns[PREFIX], &iner.as_str().to_uppercase())
+            }
+            Rule::define_keyword
+            | Rule::define_modifier
+            | Rule::variable_keyword
+            | Rule::buffer_keyword => {
+                push_word(&mut columns[PREFIX], &iner.as_str().to_uppercase())
+            }
+            Rule::define_name => columns[NAME] = iner.as_str().to_string(),
+            Rule::define_type | Rule::buffer_for => columns[TYPE] = format_define_type(iner),
+            Rule::define_initial => columns[INITIAL] = format_define_initial(iner),
+            Rule::define_option => format_define_option(iner, &mut columns, &mut options),
+            Rule::COMMENT => options.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE | Rule::statement_end => {}
+            une => panic!("unexpected define {:?}", une),
+        }
+    }
+    columns[OPTIONS] = print_list_to_line(options);
+
+    DefineColumns {
+        columns,
+        trailing_comment: None,
+        parameter,
+    }
+}
+
+fn format_define_type(define_type: Pair<Rule>) -> String {
+    let mut type_line = String::new();
+    for iner in define_type.into_inner() {
+        match iner.as_rule() {
+            Rule::aslike_keyword
+            | Rule::class_keyword
+            | Rule::keyword
+            | Rule::for_keyword
+            | Rule::temptable_keyword => push_word(&mut type_line, &iner.as_str().to_uppercase()),
+            Rule::variable | Rule::record_name | Rule::type_name => {
+                push_word(&mut type_line, iner.as_str())
+            }
+            Rule::COMMENT => push_word(&mut type_line, &format_comment(iner)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected define type {:?}", une),
+        }
+    }
+    type_line
+}
+
+fn format_define_initial(initial: Pair<Rule>) -> String {
+    let mut initial_keyword = String::new();
+    let mut print_list = Vec::new();
+    for iner in initial.into_inner() {
+        match iner.as_rule() {
+            Rule::initial_keyword => initial_keyword = iner.as_str().to_uppercase(),
+            Rule::keyword | Rule::decimal => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::variable => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::datatype => print_list.append(&mut format_datatype(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected define initial {:?}", une),
+        }
+    }
+    //joined separately so an initial array is not glued to the keyword
+    format!("{} {}", initial_keyword, print_list_to_line(print_list))
+}
+
+fn format_define_option(
+    option: Pair<Rule>,
+    columns: &mut [String; 6],
+    options: &mut Vec<PrintInfo>,
+) {
+    for iner in option.into_inner() {
+        match iner.as_rule() {
+            Rule::noundo_keyword => columns[NO_UNDO] = iner.as_str().to_uppercase(),
+            Rule::keyword | Rule::decimal => options.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::variable => {
+                options.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::comma => options.push(PrintInfo::new(
+                iner.as_str().to_string(),
+                SpaceType::NoLeftPad,
+            )),
+            Rule::datatype => options.append(&mut format_datatype(iner)),
+            Rule::function => options.append(&mut format_function(iner)),
+            une => panic!("unexpected define option {:?}", une),
+        }
+    }
+}
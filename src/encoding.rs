@@ -0,0 +1,72 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/**
+    the encoding a source was read in and whether it started with a byte order mark,
+    the formatted code is written back the same way
+*/
+pub struct SourceEncoding {
+    encoding: &'static Encoding,
+    bom: bool,
+}
+
+/**
+    decodes the source in the given encoding or the one it looks to be in, a byte order mark
+    decides it and otherwise anything that is not UTF-8 is taken as Windows-1252 which also
+    covers ISO-8859-1, returns None when a byte does not decode to a character
+*/
+pub fn decode(
+    bytes: &[u8],
+    encoding: Option<&'static Encoding>,
+) -> Option<(String, SourceEncoding)> {
+    let (encoding, bom_length) = match Encoding::for_bom(bytes) {
+        Some((bom_encoding, length)) if encoding.is_none_or(|x| x == bom_encoding) => {
+            (bom_encoding, length)
+        }
+        _ => match encoding {
+            Some(encoding) => (encoding, 0),
+            None if std::str::from_utf8(bytes).is_ok() => (UTF_8, 0),
+            None => (WINDOWS_1252, 0),
+        },
+    };
+    let text =
+        encoding.decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])?;
+
+    Some((
+        text.into_owned(),
+        SourceEncoding {
+            encoding,
+            bom: bom_length > 0,
+        },
+    ))
+}
+
+/**
+    the formatted code in the encoding of its source, None when
+    it has a character that encoding cannot hold
+*/
+pub fn encode(text: &str, source: &SourceEncoding) -> Option<Vec<u8>> {
+    //encoding_rs only writes UTF-8 for the UTF-16 encodings so they are done here
+    if source.encoding == UTF_16LE || source.encoding == UTF_16BE {
+        let bom = if source.bom { "\u{feff}" } else { "" };
+        let mut bytes = Vec::with_capacity((bom.len() + text.len()) * 2);
+        for unit in bom.encode_utf16().chain(text.encode_utf16()) {
+            if source.encoding == UTF_16LE {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return Some(bytes);
+    }
+
+    let (encoded, _, unmappable) = source.encoding.encode(text);
+    if unmappable {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(encoded.len() + 3);
+    if source.bom {
+        bytes.extend_from_slice(b"\xEF\xBB\xBF");
+    }
+    bytes.extend_from_slice(&encoded);
+    Some(bytes)
+}
@@ -1,5 +1,9 @@
 use crate::{
-    format_comment, format_whitespace, function::format_function, PrintInfo, Rule, SpaceType,
+    format_comment, format_properties, format_whitespace,
+    function::{format_call_arguments, format_function},
+    include::format_inline_include,
+    record::format_can_find,
+    PrintInfo, Rule, SpaceType,
 };
 use pest::iterators::Pair;
 
@@ -122,7 +126,6 @@ fn inner_format_expression(
             Rule::keyword
             | Rule::not_keyword
             | Rule::logical
-            | Rule::properties
             | Rule::temptable_keyword => {
                 print_list.push(PrintInfo::new(
                     format!("{}", &iner.as_span().as_str().to_uppercase()),
@@ -165,6 +168,13 @@ fn inner_format_expression(
             Rule::accumulate => {
                 print_list.append(&mut format_accumulate(iner))
             }
+            Rule::can_find => print_list.append(&mut format_can_find(iner)),
+            Rule::include => print_list.push(PrintInfo::new(
+                format_inline_include(iner),
+                SpaceType::None,
+            )),
+            Rule::properties => print_list.append(&mut format_properties(iner)),
+            Rule::new_expression => print_list.append(&mut format_new_expression(iner)),
             Rule::WHITESPACE => {
                 if *brackets == BracketFormatting::None {
                     if let Some(f) = format_whitespace(iner) {
@@ -249,6 +259,27 @@ fn inner_format_expression(
     }
 }
 
+/**
+    NEW with the class name, the arguments follow the name without a space
+*/
+fn format_new_expression(new_expression: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in new_expression.into_inner() {
+        match iner.as_rule() {
+            Rule::new_keyword => print_list.push(PrintInfo::new("NEW".to_string(), SpaceType::None)),
+            Rule::type_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::call_arguments => print_list.append(&mut format_call_arguments(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected new {:?}", une),
+        }
+    }
+    print_list
+}
+
 pub fn format_datatype(data_type: Pair<Rule>) -> Vec<PrintInfo> {
     let mut print_list = Vec::new();
 
@@ -0,0 +1,324 @@
+use crate::{
+    align_columns,
+    dataset::format_buffer_list,
+    expression::{format_datatype, format_expression},
+    format_comment, format_words,
+    print_list_to_line, push_word, FormatOptions, PrintInfo, Rule, SpaceType, TAB_WIDTH,
+};
+use pest::iterators::Pair;
+
+const ITEM: usize = 0;
+const AT: usize = 1;
+const VIEW_AS: usize = 2;
+const LABEL: usize = 3;
+const FORMAT: usize = 4;
+const OTHER: usize = 5;
+
+pub fn format_define_query(query: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    let mut buffers = Vec::new();
+    let mut options = Vec::new();
+
+    for iner in query.into_inner() {
+        match iner.as_rule() {
+            Rule::define_keyword
+            | Rule::define_modifier
+            | Rule::query_keyword
+            | Rule::for_keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::define_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::query_buffer => buffers.push(print_list_to_line(format_words(iner))),
+            Rule::keyword => options.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::datatype => options.append(&mut format_datatype(iner)),
+            Rule::COMMENT => options.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::comma | Rule::WHITESPACE => {}
+            une => panic!("unexpected define query {:?}", une),
+        }
+    }
+
+    print_list.append(&mut format_buffer_list(buffers));
+    print_list.append(&mut options);
+    print_list
+}
+
+/**
+    frame items are put one per line with their AT, VIEW-AS and LABEL phrases aligned,
+    the WITH phrase gets a line of its own
+*/
+pub fn format_define_frame(frame: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    let mut items = Vec::new();
+
+    for iner in frame.into_inner() {
+        match iner.as_rule() {
+            Rule::define_keyword | Rule::define_modifier | Rule::frame_keyword => print_list.push(
+                PrintInfo::new(iner.as_str().to_uppercase(), SpaceType::None),
+            ),
+            Rule::define_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::frame_item => items.push(iner),
+            Rule::with_phrase => {
+                print_list.append(&mut format_frame_items(items, false));
+                items = Vec::new();
+                print_list.append(&mut format_with_phrase(iner));
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected define frame {:?}", une),
+        }
+    }
+
+    print_list.append(&mut format_frame_items(items, false));
+    print_list
+}
+
+/**
+    the DISPLAY and ENABLE lists of a browse each get their own line with
+    the items one level deeper
+*/
+pub fn format_define_browse(browse: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in browse.into_inner() {
+        match iner.as_rule() {
+            Rule::define_keyword | Rule::define_modifier | Rule::browse_keyword => print_list.push(
+                PrintInfo::new(iner.as_str().to_uppercase(), SpaceType::None),
+            ),
+            Rule::define_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::browse_query => print_list.append(&mut format_words(iner)),
+            Rule::browse_display | Rule::browse_enable => {
+                let mut items = Vec::new();
+                let mut keywords = Vec::new();
+                for part in iner.into_inner() {
+                    match part.as_rule() {
+                        Rule::frame_item => items.push(part),
+                        Rule::display_keyword | Rule::enable_keyword | Rule::keyword => keywords
+                            .push(PrintInfo::new(
+                                part.as_str().to_uppercase(),
+                                SpaceType::None,
+                            )),
+                        Rule::COMMENT => {
+                            keywords.push(PrintInfo::new(format_comment(part), SpaceType::None))
+                        }
+                        _ => {}
+                    }
+                }
+                print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                print_list.push(PrintInfo::new(
+                    print_list_to_line(keywords),
+                    SpaceType::None,
+                ));
+                print_list.append(&mut format_frame_items(items, true));
+            }
+            Rule::with_phrase => print_list.append(&mut format_with_phrase(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected define browse {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    DISPLAY, MESSAGE, SET and UPDATE stay on one line while they fit, otherwise
+    the items are put one per line with their options aligned like a frame,
+    the options after the items and the WITH phrase each get a line of their own,
+    a comment ending a line always keeps the line break after it
+*/
+pub fn format_item_statement(
+    statement: Pair<Rule>,
+    indent: usize,
+    options: &FormatOptions,
+) -> Vec<PrintInfo> {
+    let mut keyword = String::new();
+    let mut rows = Vec::new();
+    let mut comments: Vec<Vec<String>> = Vec::new();
+    //the options after the items, split where a comment ends a line
+    let mut item_options = vec![Vec::new()];
+    let mut with_phrase = None;
+    let mut comment_break = false;
+    let mut after_comment = false;
+
+    for iner in statement.into_inner() {
+        let rule = iner.as_rule();
+        match rule {
+            Rule::display_keyword | Rule::message_keyword | Rule::set_update_keyword => {
+                keyword = iner.as_str().to_uppercase()
+            }
+            Rule::frame_item => {
+                comment_break |= ends_comment_line(&iner);
+                rows.push(format_frame_item(iner));
+                comments.push(Vec::new());
+            }
+            Rule::item_option => {
+                for word in format_words(iner) {
+                    if word.spacing_attribute == SpaceType::NewLine {
+                        comment_break = true;
+                        item_options.push(Vec::new());
+                    } else {
+                        let line = item_options.last_mut().expect("no item option line");
+                        line.push(word);
+                    }
+                }
+            }
+            Rule::with_phrase => with_phrase = Some(print_list_to_line(format_words(iner))),
+            //comments before the options stay with the item before them
+            Rule::COMMENT if item_options.iter().all(|x| x.is_empty()) => {
+                match comments.last_mut() {
+                    Some(row_comments) => row_comments.push(format_comment(iner)),
+                    None => push_word(&mut keyword, &format_comment(iner)),
+                }
+            }
+            Rule::COMMENT => {
+                let line = item_options.last_mut().expect("no item option line");
+                line.push(PrintInfo::new(format_comment(iner), SpaceType::None));
+            }
+            Rule::WHITESPACE => {
+                if after_comment && iner.as_str().contains('\n') {
+                    comment_break = true;
+                    if item_options.last().is_some_and(|x| !x.is_empty()) {
+                        item_options.push(Vec::new());
+                    }
+                }
+            }
+            une => panic!("unexpected item statement {:?}", une),
+        }
+        after_comment = rule == Rule::COMMENT || (after_comment && rule == Rule::WHITESPACE);
+    }
+    let item_options = item_options
+        .into_iter()
+        .map(print_list_to_line)
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut line = keyword.clone();
+    for (row, row_comments) in rows.iter().zip(&comments) {
+        for column in row.iter().chain(row_comments).filter(|x| !x.is_empty()) {
+            push_word(&mut line, column);
+        }
+    }
+    for phrase in item_options.iter().chain(with_phrase.as_ref()) {
+        push_word(&mut line, phrase);
+    }
+    //the statement end follows the last word
+    let fits = indent * TAB_WIDTH + line.len() < options.line_width;
+
+    if fits && !comment_break && !(options.one_item_per_line && rows.len() > 1) {
+        return vec![PrintInfo::new(line, SpaceType::None)];
+    }
+
+    let mut print_list = vec![PrintInfo::new(keyword, SpaceType::None)];
+    for (mut line, row_comments) in align_columns(&rows).into_iter().zip(comments) {
+        for comment in row_comments {
+            push_word(&mut line, &comment);
+        }
+        print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+        print_list.push(PrintInfo::new(line, SpaceType::None));
+    }
+    for phrase in item_options.into_iter().chain(with_phrase) {
+        print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+        print_list.push(PrintInfo::new(phrase, SpaceType::None));
+    }
+    print_list
+}
+
+/**
+    whether a comment inside the item ends a line
+*/
+fn ends_comment_line(item: &Pair<Rule>) -> bool {
+    let mut after_comment = false;
+    for part in item.clone().into_inner().flatten() {
+        match part.as_rule() {
+            Rule::WHITESPACE if after_comment && part.as_str().contains('\n') => return true,
+            Rule::WHITESPACE => {}
+            rule => after_comment = rule == Rule::COMMENT,
+        }
+    }
+    false
+}
+
+fn format_with_phrase(with_phrase: Pair<Rule>) -> Vec<PrintInfo> {
+    vec![
+        PrintInfo::new("\n".to_string(), SpaceType::NewLine),
+        PrintInfo::new(
+            print_list_to_line(format_words(with_phrase)),
+            SpaceType::None,
+        ),
+    ]
+}
+
+fn format_frame_items(items: Vec<Pair<Rule>>, extra_indent: bool) -> Vec<PrintInfo> {
+    let rows = items.into_iter().map(format_frame_item).collect::<Vec<_>>();
+
+    let mut print_list = Vec::with_capacity(rows.len() * 2);
+    for line in align_columns(&rows) {
+        print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+        if extra_indent {
+            print_list.push(PrintInfo::new(line, SpaceType::ExtraIndent));
+        } else {
+            print_list.push(PrintInfo::new(line, SpaceType::None));
+        }
+    }
+    print_list
+}
+
+fn format_frame_item(item: Pair<Rule>) -> Vec<String> {
+    let mut columns = vec![String::new(); 6];
+    let mut other = Vec::new();
+    let mut trailing_comments = Vec::new();
+
+    for iner in item.into_inner() {
+        match iner.as_rule() {
+            Rule::expression => {
+                //comments ending the item go after its options at the end of its line
+                let mut words = format_expression(iner, false);
+                while let Some(word) = words.pop() {
+                    if word.line.starts_with("/*") {
+                        trailing_comments.insert(0, word);
+                    } else if word.spacing_attribute != SpaceType::NewLine {
+                        words.push(word);
+                        break;
+                    }
+                }
+                columns[ITEM] = print_list_to_line(words)
+            }
+            Rule::skip_keyword => columns[ITEM] = iner.as_str().to_uppercase(),
+            Rule::frame_item_option => {
+                let option = iner.clone().into_inner().next();
+                let column = match option.as_ref().map(|x| x.as_rule()) {
+                    Some(Rule::at_phrase) => AT,
+                    Some(Rule::view_as_phrase) => VIEW_AS,
+                    Some(Rule::label_phrase) => LABEL,
+                    Some(Rule::keyword)
+                        if option.is_some_and(|x| x.as_str().eq_ignore_ascii_case("FORMAT")) =>
+                    {
+                        FORMAT
+                    }
+                    _ => OTHER,
+                };
+                if column == OTHER {
+                    other.append(&mut format_words(iner));
+                } else {
+                    //LABEL and COLUMN-LABEL share the label column
+                    push_word(&mut columns[column], &print_list_to_line(format_words(iner)));
+                }
+            }
+            Rule::COMMENT => other.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected frame item {:?}", une),
+        }
+    }
+    other.append(&mut trailing_comments);
+    columns[OTHER] = print_list_to_line(other);
+    columns
+}
@@ -1,5 +1,6 @@
 use crate::{
-    expression::format_expression, format_comment, format_whitespace, PrintInfo, Rule, SpaceType,
+    align_columns, expression::format_expression, format_comment, format_whitespace, format_words,
+    print_list_to_line, push_word, PrintInfo, Rule, SpaceType,
 };
 use pest::iterators::Pair;
 
@@ -39,6 +40,33 @@ pub fn format_function(function: Pair<Rule>) -> Vec<PrintInfo> {
     print_list
 }
 
+/**
+    the brackets of a method call or NEW, glued to the name before them
+*/
+pub fn format_call_arguments(call_arguments: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in call_arguments.into_inner() {
+        match iner.as_rule() {
+            Rule::left_parenthesis => {
+                print_list.push(PrintInfo::new("(".to_string(), SpaceType::NoSpace))
+            }
+            Rule::right_parenthesis => {
+                print_list.push(PrintInfo::new(")".to_string(), SpaceType::NoLeftPad))
+            }
+            Rule::function_content => print_list.append(&mut format_function_content(iner)),
+            Rule::WHITESPACE => {
+                if let Some(f) = format_whitespace(iner) {
+                    print_list.push(f);
+                }
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            une => panic!("unexpected call arguments {:?}", une),
+        }
+    }
+    print_list
+}
+
 pub fn format_function_declaration(declaration: Pair<Rule>) -> Vec<PrintInfo> {
     let mut print_list = Vec::new();
 
@@ -112,3 +140,149 @@ fn format_function_content(function_content: Pair<Rule>) -> Vec<PrintInfo> {
 
     print_list
 }
+
+const MODE: usize = 0;
+const NAME: usize = 1;
+const TYPE: usize = 2;
+const OPTIONS: usize = 3;
+
+/**
+    the FUNCTION line of a function definition along with the function name,
+    when wrapped several parameters are put one per line with their modes, names and types aligned
+*/
+pub fn format_function_header(header: Pair<Rule>, wrap: bool) -> (Vec<PrintInfo>, String) {
+    let mut print_list = Vec::new();
+    let mut name = String::new();
+
+    for iner in header.into_inner() {
+        match iner.as_rule() {
+            Rule::function_keyword
+            | Rule::returns_keyword
+            | Rule::class_keyword
+            | Rule::keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::define_name => {
+                name = iner.as_str().to_string();
+                print_list.push(PrintInfo::new(name.clone(), SpaceType::None));
+            }
+            Rule::type_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::function_option => print_list.append(&mut format_words(iner)),
+            Rule::function_parameters => {
+                print_list.append(&mut format_function_parameters(iner, wrap))
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected function header {:?}", une),
+        }
+    }
+    (print_list, name)
+}
+
+pub fn format_function_parameters(parameters: Pair<Rule>, wrap: bool) -> Vec<PrintInfo> {
+    let mut rows = Vec::new();
+    let mut comments: Vec<Vec<String>> = Vec::new();
+
+    for iner in parameters.into_inner() {
+        match iner.as_rule() {
+            Rule::function_parameter => {
+                rows.push(format_function_parameter(iner));
+                comments.push(Vec::new());
+            }
+            //comments stay with the parameter before them
+            Rule::COMMENT => match comments.last_mut() {
+                Some(row_comments) => row_comments.push(format_comment(iner)),
+                None => comments.push(vec![format_comment(iner)]),
+            },
+            Rule::left_parenthesis | Rule::right_parenthesis | Rule::comma | Rule::WHITESPACE => {}
+            une => panic!("unexpected function parameters {:?}", une),
+        }
+    }
+    format_parameter_rows(rows, comments, wrap)
+}
+
+/**
+    the parameters stay on the line they belong to unless they are wrapped,
+    several wrapped parameters are put one per line with their columns aligned,
+    parameters with comments after them are always wrapped
+*/
+pub fn format_parameter_rows(
+    rows: Vec<Vec<String>>,
+    comments: Vec<Vec<String>>,
+    wrap: bool,
+) -> Vec<PrintInfo> {
+    let commented = comments.iter().any(|x| !x.is_empty());
+    if rows.len() < 2 || !(wrap || commented) {
+        let parameters = rows
+            .iter()
+            .map(|row| {
+                let words = row.iter().filter(|column| !column.is_empty());
+                words.cloned().collect::<Vec<_>>().join(" ")
+            })
+            .collect::<Vec<_>>();
+        let mut line = format!("({})", parameters.join(", "));
+        for comment in comments.into_iter().flatten() {
+            push_word(&mut line, &comment);
+        }
+        return vec![PrintInfo::new(line, SpaceType::None)];
+    }
+
+    let mut print_list = vec![PrintInfo::new("(".to_string(), SpaceType::None)];
+    let last = rows.len() - 1;
+    for (i, (mut line, row_comments)) in align_columns(&rows).into_iter().zip(comments).enumerate()
+    {
+        if i == last {
+            line.push(')');
+        } else {
+            line.push(',');
+        }
+        for comment in row_comments {
+            push_word(&mut line, &comment);
+        }
+        print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+        print_list.push(PrintInfo::new(line, SpaceType::None));
+    }
+    print_list
+}
+
+fn format_function_parameter(parameter: Pair<Rule>) -> Vec<String> {
+    let mut columns = vec![String::new(); 4];
+
+    for iner in parameter.into_inner() {
+        match iner.as_rule() {
+            Rule::parameter_mode => columns[MODE] = iner.as_str().to_uppercase(),
+            Rule::define_name => columns[NAME] = iner.as_str().to_string(),
+            //the table keyword or buffer name sits in the name column, the FOR part in the type column
+            Rule::parameter_table => {
+                for part in iner.into_inner() {
+                    match part.as_rule() {
+                        Rule::parameter_table_keyword | Rule::buffer_keyword => {
+                            push_word(&mut columns[NAME], &part.as_str().to_uppercase())
+                        }
+                        Rule::define_name => push_word(&mut columns[NAME], part.as_str()),
+                        Rule::for_keyword | Rule::temptable_keyword => {
+                            push_word(&mut columns[TYPE], &part.as_str().to_uppercase())
+                        }
+                        Rule::record_name if columns[TYPE].is_empty() => {
+                            push_word(&mut columns[NAME], part.as_str())
+                        }
+                        Rule::record_name => push_word(&mut columns[TYPE], part.as_str()),
+                        _ => {}
+                    }
+                }
+            }
+            Rule::define_type => columns[TYPE] = print_list_to_line(format_words(iner)),
+            Rule::parameter_option => push_word(
+                &mut columns[OPTIONS],
+                &print_list_to_line(format_words(iner)),
+            ),
+            Rule::COMMENT => push_word(&mut columns[OPTIONS], &format_comment(iner)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected function parameter {:?}", une),
+        }
+    }
+    columns
+}
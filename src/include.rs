@@ -0,0 +1,71 @@
+use crate::{FormatOptions, Rule, TAB_WIDTH};
+use pest::iterators::Pair;
+
+/**
+    an include reference as the lines it is printed on, the arguments are put
+    one per line aligned after the file name when asked to or when they do not fit
+*/
+pub fn format_include(include: Pair<Rule>, indent: usize, options: &FormatOptions) -> Vec<String> {
+    let (line, file, arguments) = match include_parts(include) {
+        Ok(parts) => parts,
+        Err(text) => return vec![text],
+    };
+    let fits = indent * TAB_WIDTH + line.len() <= options.line_width;
+    if (fits && !options.include_args_per_line) || arguments.len() < 2 {
+        return vec![line];
+    }
+
+    let first = format!("{{{} ", file);
+    let padding = " ".repeat(first.len());
+    let last = arguments.len() - 1;
+    arguments
+        .into_iter()
+        .enumerate()
+        .map(|(i, argument)| {
+            let start = if i == 0 { &first } else { &padding };
+            if i == last {
+                format!("{}{}}}", start, argument)
+            } else {
+                format!("{}{}", start, argument)
+            }
+        })
+        .collect()
+}
+
+/**
+    an include within a statement, always kept on one line
+*/
+pub fn format_inline_include(include: Pair<Rule>) -> String {
+    match include_parts(include) {
+        Ok((line, _, _)) => line,
+        Err(text) => text,
+    }
+}
+
+/**
+    the include on one line along with its file name and arguments,
+    references that are not a file with arguments are kept as written
+*/
+fn include_parts(include: Pair<Rule>) -> Result<(String, String, Vec<String>), String> {
+    let text = include.as_str().to_string();
+    let mut file = None;
+    let mut arguments = Vec::new();
+
+    for iner in include.into_inner() {
+        match iner.as_rule() {
+            Rule::include_file => file = Some(iner.as_str().to_string()),
+            Rule::include_argument => arguments.push(iner.as_str().to_string()),
+            Rule::inner_include | Rule::include | Rule::WHITESPACE => {}
+            une => panic!("unexpected include {:?}", une),
+        }
+    }
+    let file = file.ok_or(text)?;
+
+    let mut line = format!("{{{}", file);
+    for argument in arguments.iter() {
+        line.push(' ');
+        line.push_str(argument);
+    }
+    line.push('}');
+    Ok((line, file, arguments))
+}
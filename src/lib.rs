@@ -1,23 +1,51 @@
 extern crate pest;
 mod assign;
+mod block;
+mod class;
+mod conditional;
+mod dataset;
+mod define;
+mod encoding;
 mod expression;
+mod frame;
 mod function;
+mod include;
+mod record;
+mod run;
 mod temp_table;
+mod trigger;
+mod using;
 
 use std::{
     fs::File,
-    io::{stdin, BufRead, BufReader},
+    io::{stdin, stdout, BufRead, BufReader, Write},
 };
 
 use lazy_static::lazy_static;
 
+use pad::PadStr;
 use regex::Regex;
 
 use assign::format_assign;
+use block::format_block_header;
+use class::{format_class_header, format_method_header, format_property_accessor};
+use conditional::{format_case_header, format_if_then, format_when_then};
+use dataset::{format_define_data_source, format_define_dataset};
+use define::format_define_variables;
+use encoding::{decode, encode};
+use encoding_rs::Encoding;
 use expression::{format_by_expression,format_accumulate,format_conditional_expression, format_datatype, format_expression};
-use function::format_function_declaration;
+use frame::{format_define_browse, format_define_frame, format_define_query, format_item_statement};
+use function::{
+    format_call_arguments, format_function, format_function_declaration, format_function_header,
+};
+use include::format_include;
 use pest::{iterators::Pair, Parser};
-use temp_table::format_temp_table;
+use record::{format_find_phrase, format_record_phrase, format_record_phrases};
+use run::format_run_statement;
+use temp_table::{format_temp_table, format_temp_table_fragment};
+use trigger::{format_on_trigger, format_wait_for};
+use using::format_using_statements;
 
 #[macro_use]
 extern crate pest_derive;
@@ -41,6 +69,8 @@ enum SpaceType {
     NoRightPad,
     TabPadRight,
     NoSpace,
+    Padded,    //printed untrimmed so alignment padding is kept
+    LineBreak, //a new line at the indentation of the statement itself
 }
 pub struct PrintInfo {
     line: String,
@@ -56,11 +86,107 @@ impl PrintInfo {
     }
 }
 
+/**
+    where a part of a statement goes relative to the line before it
+*/
+#[derive(PartialEq, Clone, Copy)]
+pub enum LinePlacement {
+    Keep, //as it is in the source
+    SameLine,
+    NextLine,
+}
+
+/**
+    the line ending written after every line
+*/
+#[derive(Clone, Copy)]
+pub enum EndOfLine {
+    Auto, //whichever ending most lines of the source have
+    Lf,
+    Crlf,
+}
+
+/**
+    settings that change how the code is laid out
+*/
+pub struct FormatOptions {
+    pub procedure_blank_lines: usize, //blank lines kept before and after a procedure, function or method
+    pub procedure_end_comment: bool,  //name the procedure or function in a comment after its END
+    pub sort_using: bool,             //sort runs of USING statements and drop repeats
+    pub then_placement: LinePlacement, //THEN against the end of the IF condition
+    pub else_if_placement: LinePlacement, //the IF of an ELSE IF against its ELSE
+    pub if_branch_placement: LinePlacement, //a single statement against its THEN or ELSE
+    pub split_conditions: bool, //one AND/OR condition per line in WHERE and IF
+    pub line_width: usize,      //statements with item lists are wrapped past this width
+    pub one_item_per_line: bool, //always wrap the items of DISPLAY, MESSAGE and UPDATE
+    pub split_event_lists: bool, //one event per line in ON and WAIT-FOR
+    pub label_placement: LinePlacement, //a block label against the block it labels
+    pub label_end_comment: bool, //name the label in a comment after the END of its block
+    pub include_args_per_line: bool, //always put the arguments of an include one per line
+    pub fragment: bool,     //an include file that may not be whole statements
+    pub base_indent: usize, //the nesting level the file is included at
+    pub encoding: Option<&'static Encoding>, //the encoding of the source, detected when not given
+    pub end_of_line: EndOfLine,
+    pub max_blank_lines: usize, //longer runs of blank lines are shortened to this
+    pub define_blank_lines: Option<usize>, //blank lines between a run of DEFINEs and what follows
+    pub block_edge_blank_lines: Option<usize>, //blank lines at the start and end of a block
+    pub align_trailing_comments: bool, //line up the comments ending consecutive lines of code
+    pub trailing_comment_gap: usize,   //the least spaces between code and the comment after it
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            procedure_blank_lines: 1,
+            procedure_end_comment: false,
+            sort_using: false,
+            then_placement: LinePlacement::Keep,
+            else_if_placement: LinePlacement::Keep,
+            if_branch_placement: LinePlacement::Keep,
+            split_conditions: false,
+            line_width: 120,
+            one_item_per_line: false,
+            split_event_lists: false,
+            label_placement: LinePlacement::NextLine,
+            label_end_comment: false,
+            include_args_per_line: false,
+            fragment: false,
+            base_indent: 0,
+            encoding: None,
+            end_of_line: EndOfLine::Auto,
+            max_blank_lines: 2,
+            define_blank_lines: None,
+            block_edge_blank_lines: None,
+            align_trailing_comments: false,
+            trailing_comment_gap: 1,
+        }
+    }
+}
+
+//the width of a tab when measuring lines
+const TAB_WIDTH: usize = 4;
+
+//put before a comment that ends a line of code, replaced by the padding once the lines are known
+const COMMENT_MARK: char = '\u{fdd0}';
+
+/**
+    blocks that are still open, an END closes the innermost one
+*/
+enum BlockType {
+    Procedure(String),
+    Function(String), //closes with END FUNCTION but is otherwise handled as a procedure
+    Keyword(String), //the END repeats the keyword that opened the block
+    Property,        //has no END, its accessors are over once anything else follows
+    Hanging(bool),   //a branch on its own line, true when it is a nested IF that an ELSE can follow
+    Label(String),   //a labelled block, the END can name the label in a comment
+    Other,
+}
+
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct InputParser;
 
-pub fn format_code(input: IoType) {
+pub fn format_code(input: IoType, options: &FormatOptions) {
     //general handling of input for either the console or a file
     let mut input: Box<dyn BufRead> = match input {
         IoType::FromStdIn => Box::new(BufReader::new(stdin())),
@@ -75,51 +201,430 @@ pub fn format_code(input: IoType) {
     let mut buf = Vec::new();
     input.read_to_end(&mut buf).expect("error reading in input");
 
-    let buf = String::from_utf8_lossy(&buf);
+    //nothing is written for a source that would not come back byte for byte
+    let (buf, source_encoding) =
+        decode(&buf, options.encoding).expect("input is not valid in its encoding");
+    let end_of_line = match options.end_of_line {
+        EndOfLine::Auto => {
+            let crlf_count = buf.matches("\r\n").count();
+            if crlf_count > buf.matches('\n').count() - crlf_count {
+                EndOfLine::Crlf
+            } else {
+                EndOfLine::Lf
+            }
+        }
+        end_of_line => end_of_line,
+    };
+    let options = &FormatOptions {
+        end_of_line,
+        ..*options
+    };
 
-    let sucessful_parse = InputParser::parse(Rule::program, &buf).expect("unsucessful parse");
+    //a fragment is tried first as it can also read as a program of loose statements,
+    //the error of the program is the more useful one when neither parses
+    let fragment = if options.fragment {
+        InputParser::parse(Rule::fragment, &buf).ok()
+    } else {
+        None
+    };
+    let sucessful_parse = match fragment {
+        Some(parse) => parse,
+        None => InputParser::parse(Rule::program, &buf)
+            .unwrap_or_else(|error| panic!("unsucessful parse: {:?}", error)),
+    };
 
-    let mut indent_level = 0;
+    let mut out = String::new();
+    let mut blocks = (0..options.base_indent)
+        .map(|_| BlockType::Other)
+        .collect::<Vec<_>>();
+    let mut pending_newlines = 0;
+    let mut line_started = false;
+    let mut procedure_closed = false;
+    let mut routine_comment = false;
+    let mut block_opened = false;
+    let mut after_defines = false;
+    let mut statement_wrapped = false;
     for parse_pair in sucessful_parse {
-        for iner in parse_pair.into_inner() {
+        let items = parse_pair.into_inner().collect::<Vec<_>>();
+        for (i, iner) in items.iter().cloned().enumerate() {
+            //newlines are held back until the next item so blank lines around procedures can be adjusted
+            match iner.as_rule() {
+                Rule::WHITESPACE | Rule::NEWLINE => {
+                    if iner.as_str().contains('\n') {
+                        pending_newlines += 1;
+                    }
+                    continue;
+                }
+                Rule::EOI => {}
+                _ => {
+                    //the file starts with its first line of code or comment
+                    if out.is_empty() {
+                        pending_newlines = 0;
+                    }
+                    //an ELSE after a branch on its own line starts a line at the indent of its IF
+                    if statement_wrapped && pending_newlines == 0 && starts_with(&iner, Rule::else_keyword) {
+                        pending_newlines = 1;
+                    }
+                    //comments leading into a routine take the blank lines before it
+                    let leads_routine =
+                        iner.as_rule() == Rule::COMMENT && leads_into_routine(&items[i + 1..]);
+                    if line_started && pending_newlines > 0 {
+                        let at_block_edge = block_opened || starts_with(&iner, Rule::block_end);
+                        let after_define_run = after_defines && !is_define(&iner);
+                        let after_routine = procedure_closed && !starts_with(&iner, Rule::block_end);
+                        let before_routine =
+                            (leads_routine || starts_routine(&iner)) && !routine_comment;
+                        pending_newlines = if after_routine || before_routine {
+                            options.procedure_blank_lines + 1
+                        } else if let (Some(blank_lines), true) =
+                            (options.block_edge_blank_lines, at_block_edge)
+                        {
+                            blank_lines + 1
+                        } else if let (Some(blank_lines), true) =
+                            (options.define_blank_lines, after_define_run)
+                        {
+                            blank_lines + 1
+                        } else {
+                            pending_newlines.min(options.max_blank_lines + 1)
+                        };
+                        procedure_closed = false;
+                    }
+                    //a comment after something on its line belongs to that line
+                    if iner.as_rule() != Rule::COMMENT || !line_started || pending_newlines > 0 {
+                        block_opened = false;
+                        after_defines = is_define(&iner);
+                        routine_comment = leads_routine;
+                    }
+                }
+            }
+            for _ in 0..pending_newlines {
+                new_line(&mut out, options);
+            }
+            if pending_newlines > 0 {
+                line_started = false;
+            }
+            pending_newlines = 0;
+
+            //blocks without an END are over once something other than their continuation follows
+            if !matches!(iner.as_rule(), Rule::COMMENT | Rule::EOI) {
+                while match blocks.last() {
+                    Some(BlockType::Property) => !starts_with(&iner, Rule::property_accessor),
+                    Some(BlockType::Hanging(true)) => !starts_with(&iner, Rule::else_keyword),
+                    //the level of an ELSE that has been used only lasts until what it holds is over
+                    Some(BlockType::Hanging(false)) => true,
+                    _ => false,
+                } {
+                    blocks.pop();
+                }
+            }
+
+            let indent_level = blocks.len();
             match iner.as_rule() {
                 Rule::COMMENT => {
-                    print!("{}{}", get_tabs(indent_level), format_comment(iner));
+                    if line_started {
+                        out.push(COMMENT_MARK);
+                        out.push_str(&format_comment(iner));
+                    } else {
+                        out.push_str(&get_tabs(indent_level));
+                        out.push_str(&format_comment(iner));
+                    }
                 }
-                Rule::statement => indent_level = format_statement(iner.clone(), indent_level),
-                Rule::WHITESPACE => {
-                    if iner.as_str().contains("\n") {
-                        println!();
-                    } else if let Some(potential_newline) = iner.into_inner().next() {
-                        if let Rule::NEWLINE = potential_newline.as_rule() {
-                            println!()
+                Rule::statement | Rule::fragment_statement => {
+                    block_opened = iner.clone().into_inner().any(|x| x.as_rule() == Rule::block_begin);
+                    let statement_start = out.len();
+                    let closed_block = format_statement(iner, &mut blocks, line_started, options, &mut out);
+                    statement_wrapped = out[statement_start..].contains('\n');
+                    let end_comment = match closed_block {
+                        Some(BlockType::Procedure(name)) | Some(BlockType::Function(name)) => {
+                            procedure_closed = true;
+                            Some(name).filter(|_| options.procedure_end_comment)
+                        }
+                        Some(BlockType::Label(name)) => Some(name).filter(|_| options.label_end_comment),
+                        Some(BlockType::Keyword(keyword)) => {
+                            procedure_closed |= matches!(keyword.as_str(), "METHOD" | "CONSTRUCTOR" | "DESTRUCTOR");
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(name) = end_comment {
+                        //an existing comment after the END is kept instead
+                        let commented = items[i + 1..]
+                            .iter()
+                            .take_while(|x| x.as_rule() == Rule::WHITESPACE && !x.as_str().contains('\n'))
+                            .count();
+                        let has_comment = items
+                            .get(i + 1 + commented)
+                            .is_some_and(|x| x.as_rule() == Rule::COMMENT);
+                        if !has_comment {
+                            out.push_str(&format!("/* {} */", name));
                         }
                     }
                 }
-                Rule::keyword => print!("{}", iner.as_span().as_str().to_uppercase()),
+                Rule::keyword => out.push_str(&iner.as_span().as_str().to_uppercase()),
                 Rule::include => {
-                    println!("{}{}", get_tabs(indent_level), iner.as_span().as_str())
+                    for (i, line) in format_include(iner, indent_level, options).iter().enumerate() {
+                        if i > 0 {
+                            new_line(&mut out, options);
+                        }
+                        if i > 0 || !line_started {
+                            out.push_str(&get_tabs(indent_level));
+                        }
+                        out.push_str(line);
+                    }
                 }
-                Rule::NEWLINE => {
-                    println!();
+                Rule::using_statements | Rule::define_variables => {
+                    let mut lines = if iner.as_rule() == Rule::using_statements {
+                        format_using_statements(iner, options.sort_using)
+                    } else {
+                        format_define_variables(
+                            iner,
+                            matches!(blocks.last(), Some(BlockType::Procedure(_))),
+                        )
+                    };
+                    //newlines after the run are printed with the ones that follow it
+                    while lines.last().is_some_and(|line| line.is_empty()) {
+                        lines.pop();
+                        pending_newlines += 1;
+                    }
+                    for (i, line) in lines.iter().enumerate() {
+                        if i > 0 {
+                            new_line(&mut out, options);
+                        }
+                        if !line.is_empty() {
+                            out.push_str(&get_tabs(indent_level));
+                            out.push_str(line);
+                        }
+                    }
+                    //same trailing space as a statement end so trailing comments stay separated
+                    out.push(' ');
                 }
                 Rule::EOI => {}
                 _ => panic!("unrecongised program {:?}", iner.as_rule()),
             };
+            line_started = true;
+        }
+    }
+
+    //the file ends with exactly one newline
+    out.truncate(out.trim_end().len());
+    if !out.is_empty() {
+        new_line(&mut out, options);
+    }
+
+    let out = align_trailing_comments(&out, options);
+    let bytes = encode(&out, &source_encoding).expect("output cannot be written in the source encoding");
+    stdout().write_all(&bytes).expect("error writing output");
+}
+
+/**
+    whether the item starts a procedure, function or method that has a body
+*/
+fn starts_routine(item: &Pair<Rule>) -> bool {
+    [
+        Rule::procedure_header,
+        Rule::function_header,
+        Rule::method_header,
+        Rule::constructor_header,
+    ]
+    .iter()
+    .any(|rule| starts_with(item, *rule))
+        && item.clone().into_inner().any(|x| x.as_rule() == Rule::block_begin)
+}
+
+/**
+    whether the items after a comment lead into a routine through more
+    comments with no blank line in between
+*/
+fn leads_into_routine(items: &[Pair<Rule>]) -> bool {
+    let mut newlines = 0;
+    for item in items {
+        match item.as_rule() {
+            Rule::WHITESPACE | Rule::NEWLINE => {
+                if item.as_str().contains('\n') {
+                    newlines += 1;
+                }
+                if newlines > 1 {
+                    return false;
+                }
+            }
+            Rule::COMMENT => newlines = 0,
+            _ => return starts_routine(item),
         }
     }
+    false
+}
+
+/**
+    whether the item is a DEFINE of any kind
+*/
+fn is_define(item: &Pair<Rule>) -> bool {
+    let first_word = item
+        .as_str()
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+    match item.as_rule() {
+        Rule::define_variables => true,
+        Rule::statement => first_word.len() >= 3 && "DEFINE".starts_with(&first_word),
+        _ => false,
+    }
+}
+
+/**
+    whether the item is a statement that starts with the given rule
+*/
+fn starts_with(item: &Pair<Rule>, rule: Rule) -> bool {
+    item.as_rule() == Rule::statement
+        && item
+            .clone()
+            .into_inner()
+            .next()
+            .is_some_and(|x| x.as_rule() == rule)
 }
 
-fn format_statement(statement: Pair<Rule>, indent_level: usize) -> usize {
+/**
+    prints a statement and opens or closes any block it starts or ends,
+    returns the procedure or labelled block the statement closes
+*/
+fn format_statement(
+    statement: Pair<Rule>,
+    blocks: &mut Vec<BlockType>,
+    line_started: bool,
+    options: &FormatOptions,
+    out: &mut String,
+) -> Option<BlockType> {
     let mut print_list = Vec::new();
-    let mut next_indent = indent_level;
+    let indent_level = blocks.len();
+    let mut block_type = BlockType::Other;
+    let mut closed_block = None;
+    let opens_block = statement
+        .clone()
+        .into_inner()
+        .any(|x| x.as_rule() == Rule::block_begin);
+    //set after THEN or ELSE until the branch itself starts
+    let mut branch = None;
+    let mut branch_newlines = 0;
+    //a comment before the branch, and whether it moved to a line of its own
+    let mut branch_comment = None;
+    let mut hanging = None;
+    //set after a label until the labelled statement starts
+    let mut label = None;
+    let mut label_newlines = None;
+    let fragment = statement.as_rule() == Rule::fragment_statement;
+    //an ELSE ends the nested IF it lines up with unless another IF carries on from its line
+    let else_of_hanging = matches!(blocks.last(), Some(BlockType::Hanging(true)))
+        && starts_with(&statement, Rule::else_keyword);
+    let has_if = statement
+        .clone()
+        .into_inner()
+        .any(|x| x.as_rule() == Rule::if_then);
 
     /* statements require deeper analysis as printing current words can be context sensitive to later upcoming words
      */
     for iner in statement.into_inner() {
+        if let Some(newlines) = label_newlines {
+            match iner.as_rule() {
+                Rule::WHITESPACE | Rule::NEWLINE => {
+                    if iner.as_str().contains('\n') {
+                        label_newlines = Some(newlines + 1);
+                    }
+                    continue;
+                }
+                rule => {
+                    let newline = match options.label_placement {
+                        _ if rule == Rule::COMMENT => newlines > 0,
+                        LinePlacement::Keep => newlines > 0,
+                        LinePlacement::SameLine => false,
+                        LinePlacement::NextLine => true,
+                    };
+                    if newline {
+                        print_list.push(PrintInfo::new("\n".to_string(), SpaceType::LineBreak));
+                    }
+                    label_newlines = None;
+                }
+            }
+        }
+        if let Some(after) = branch {
+            match iner.as_rule() {
+                Rule::WHITESPACE | Rule::NEWLINE => {
+                    if iner.as_str().contains('\n') {
+                        branch_newlines += 1;
+                    }
+                    continue;
+                }
+                //the comment keeps its line and the line break after it, so the branch follows it as written
+                Rule::COMMENT => {
+                    let moved = branch_newlines > 0;
+                    if moved {
+                        push_branch_line(&mut print_list, hanging);
+                    }
+                    branch_comment = Some(branch_comment.unwrap_or_default() || moved);
+                    branch_newlines = 0;
+                }
+                rule => {
+                    let placement = match rule {
+                        Rule::if_then if after == Rule::else_keyword => &options.else_if_placement,
+                        _ if branch_comment.is_some() => &LinePlacement::Keep,
+                        _ if opens_block => &LinePlacement::Keep,
+                        //the action of a WHEN or OTHERWISE is always one level further
+                        _ if after == Rule::when_then || after == Rule::otherwise_keyword => {
+                            &LinePlacement::NextLine
+                        }
+                        Rule::if_then => &LinePlacement::Keep,
+                        _ if after == Rule::on_trigger => &LinePlacement::Keep,
+                        _ => &options.if_branch_placement,
+                    };
+                    let newline = match placement {
+                        LinePlacement::Keep => branch_newlines > 0,
+                        LinePlacement::SameLine => false,
+                        LinePlacement::NextLine => true,
+                    };
+                    if newline {
+                        push_branch_line(&mut print_list, hanging);
+                    }
+                    if newline || branch_comment == Some(true) {
+                        hanging = Some(hanging == Some(true) || rule == Rule::if_then);
+                    }
+                    branch = None;
+                    branch_newlines = 0;
+                    branch_comment = None;
+                }
+            }
+        }
         //println!("{:?}",iner.as_rule());
         match iner.as_rule() {
-            Rule::loop_label | Rule::comma |Rule::properties_sigil => {
+            Rule::if_then => {
+                print_list.append(&mut format_if_then(iner, options));
+                branch = Some(Rule::if_then);
+            }
+            Rule::else_keyword => {
+                print_list.push(PrintInfo::new("ELSE".to_string(), SpaceType::None));
+                branch = Some(Rule::else_keyword);
+            }
+            Rule::case_header => {
+                print_list.append(&mut format_case_header(iner));
+                block_type = BlockType::Keyword("CASE".to_string());
+            }
+            Rule::when_then => {
+                print_list.append(&mut format_when_then(iner));
+                branch = Some(Rule::when_then);
+            }
+            Rule::otherwise_keyword => {
+                print_list.push(PrintInfo::new("OTHERWISE".to_string(), SpaceType::None));
+                branch = Some(Rule::otherwise_keyword);
+            }
+            Rule::on_trigger => {
+                print_list.append(&mut format_on_trigger(iner, options.split_event_lists));
+                branch = Some(Rule::on_trigger);
+            }
+            Rule::loop_label => {
+                let text = iner.as_str();
+                label = Some(text.trim_end_matches(':').to_string());
+                label_newlines = Some(0);
+                print_list.push(PrintInfo::new(text.to_string(), SpaceType::None));
+            }
+            Rule::comma |Rule::properties_sigil => {
                 print_list.push(PrintInfo::new(
                     format!("{}", iner.as_span().as_str()),
                     SpaceType::NoLeftPad,
@@ -152,22 +657,122 @@ fn format_statement(statement: Pair<Rule>, indent_level: usize) -> usize {
                     format!("{}", iner.as_span().as_str()),
                     SpaceType::NoLeftPad,
                 ));
-                next_indent += 1;
+                if let Some(nested_if) = hanging {
+                    blocks.push(BlockType::Hanging(nested_if));
+                }
+                if let (BlockType::Other, Some(name)) = (&block_type, label.take()) {
+                    block_type = BlockType::Label(name);
+                }
+                blocks.push(std::mem::replace(&mut block_type, BlockType::Other));
+            }
+            Rule::procedure_header => {
+                let (mut header, name) = format_procedure_header(iner);
+                print_list.append(&mut header);
+                block_type = BlockType::Procedure(name);
             }
+            Rule::function_header => {
+                let one_line = format_function_header(iner.clone(), false).0;
+                let wrap = !fits_line(one_line, indent_level, options);
+                let (mut header, name) = format_function_header(iner, wrap);
+                print_list.append(&mut header);
+                block_type = BlockType::Function(name);
+            }
+            Rule::class_header => {
+                let (mut header, keyword) = format_class_header(iner);
+                print_list.append(&mut header);
+                block_type = BlockType::Keyword(keyword);
+            }
+            Rule::method_header | Rule::constructor_header => {
+                let one_line = format_method_header(iner.clone(), false).0;
+                let wrap = !fits_line(one_line, indent_level, options);
+                let (mut header, keyword) = format_method_header(iner, wrap);
+                print_list.append(&mut header);
+                block_type = BlockType::Keyword(keyword);
+            }
+            Rule::define_property => {
+                print_list.append(&mut format_words(iner));
+                blocks.push(BlockType::Property);
+            }
+            Rule::property_accessor => {
+                let (mut accessor, keyword) = format_property_accessor(iner);
+                print_list.append(&mut accessor);
+                block_type = BlockType::Keyword(keyword);
+            }
+            Rule::catch_header => {
+                print_list.append(&mut format_words(iner));
+                block_type = BlockType::Keyword("CATCH".to_string());
+            }
+            Rule::finally_keyword => {
+                print_list.push(PrintInfo::new("FINALLY".to_string(), SpaceType::None));
+                block_type = BlockType::Keyword("FINALLY".to_string());
+            }
+            Rule::block_level_keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::on_error_phrase => print_list.append(&mut format_words(iner)),
+            Rule::block_header => print_list.append(&mut format_block_header(
+                iner,
+                blocks.len(),
+                options,
+            )),
+            Rule::run_statement => {
+                let one_line = format_run_statement(iner.clone(), false);
+                let wrap = !fits_line(one_line, indent_level, options);
+                print_list.append(&mut format_run_statement(iner, wrap))
+            }
+            Rule::wait_for_statement => {
+                print_list.append(&mut format_wait_for(iner, options.split_event_lists))
+            }
+            Rule::item_statement => print_list.append(&mut format_item_statement(
+                iner,
+                blocks.len(),
+                options,
+            )),
+            Rule::record_phrases => print_list.append(&mut format_record_phrases(iner, options)),
+            Rule::find_phrase => print_list.append(&mut format_find_phrase(iner, options)),
+            Rule::fragment_table => print_list.append(&mut format_temp_table_fragment(iner)),
+            Rule::fragment_phrase => {
+                print_list.append(&mut format_record_phrase(iner, options.split_conditions))
+            }
+            Rule::operator => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
             Rule::block_end => {
-                print_list.push(PrintInfo::new(
-                    format!("{}", &iner.as_span().as_str()).to_uppercase(),
-                    SpaceType::End,
-                ));
-                if next_indent > 0 {
-                    next_indent -= 1
-                } else {
-                    next_indent = 0
+                let end = match blocks.pop() {
+                    Some(BlockType::Procedure(name)) => {
+                        closed_block = Some(BlockType::Procedure(name));
+                        "END PROCEDURE.".to_string()
+                    }
+                    Some(BlockType::Function(name)) => {
+                        closed_block = Some(BlockType::Function(name));
+                        "END FUNCTION.".to_string()
+                    }
+                    Some(BlockType::Keyword(keyword)) => {
+                        let end = format!("END {}.", keyword);
+                        closed_block = Some(BlockType::Keyword(keyword));
+                        end
+                    }
+                    Some(BlockType::Label(name)) => {
+                        closed_block = Some(BlockType::Label(name));
+                        iner.as_str().to_uppercase()
+                    }
+                    _ => iner.as_str().to_uppercase(),
                 };
+                print_list.push(PrintInfo::new(end, SpaceType::End));
+                if matches!(blocks.last(), Some(BlockType::Hanging(false))) {
+                    blocks.pop();
+                }
             }
             Rule::define_temp_table => {
                 print_list.append(&mut format_temp_table(iner));
             }
+            Rule::define_dataset => print_list.append(&mut format_define_dataset(iner)),
+            Rule::define_data_source => print_list.append(&mut format_define_data_source(iner)),
+            Rule::define_query => print_list.append(&mut format_define_query(iner)),
+            Rule::define_browse => print_list.append(&mut format_define_browse(iner)),
+            Rule::define_frame => print_list.append(&mut format_define_frame(iner)),
             Rule::assign_statement => {
                 print_list.append(&mut format_assign(iner));
             }
@@ -177,18 +782,26 @@ fn format_statement(statement: Pair<Rule>, indent_level: usize) -> usize {
                 }
             }
             Rule::NEWLINE => {
-                println!();
+                new_line(out, options);
             }
             Rule::statement_end => print_list.push(PrintInfo::new(
                 format!("{}", &iner.as_span().as_str().to_uppercase()),
                 SpaceType::End,
             )),
             Rule::datatype => print_list.append(&mut format_datatype(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
 
             _ => eprint!("+++++{:?}+++++++", iner.as_rule()),
         }
     }
 
+    //the line breaks an expression takes in at the end of a fragment are printed after it
+    let mut trailing_newlines = 0;
+    while fragment && print_list.last().is_some_and(|x: &PrintInfo| x.spacing_attribute == SpaceType::NewLine) {
+        print_list.pop();
+        trailing_newlines += 1;
+    }
+
     /*actual printing of the words */
     let mut words_iter = print_list.into_iter().enumerate().peekable();
     let mut prev_spacing = None;
@@ -199,22 +812,33 @@ fn format_statement(statement: Pair<Rule>, indent_level: usize) -> usize {
                 break;
             }
         };
+        //a statement sharing its line with the previous one is not indented
         if i == 0 && word.spacing_attribute != SpaceType::End {
-            print!("{}{}", get_tabs(indent_level), word.line.trim());
+            if line_started {
+                out.push_str(word.line.trim());
+            } else {
+                out.push_str(&get_tabs(indent_level));
+                out.push_str(word.line.trim());
+            }
             continue;
         }
         if word.spacing_attribute == SpaceType::End {
+            //only an END starting the line is dedented, a statement end follows the last word
             if words_iter.peek().is_none() {
-                if indent_level == 0 {
-                    print!("{} ", word.line.trim())
+                if i > 0 || indent_level == 0 || line_started {
+                    out.push_str(word.line.trim());
+                    out.push(' ');
                 } else {
-                    print!("{}{} ", get_tabs(indent_level - 1), word.line.trim());
+                    out.push_str(&get_tabs(indent_level - 1));
+                    out.push_str(word.line.trim());
+                    out.push(' ');
                 }
             } else {
-                if indent_level == 0 {
-                    print!("{}", word.line.trim())
+                if i > 0 || indent_level == 0 || line_started {
+                    out.push_str(word.line.trim())
                 } else {
-                    print!("{}{}", get_tabs(indent_level - 1), word.line.trim());
+                    out.push_str(&get_tabs(indent_level - 1));
+                    out.push_str(word.line.trim());
                 }
             }
             continue;
@@ -225,39 +849,71 @@ fn format_statement(statement: Pair<Rule>, indent_level: usize) -> usize {
                 //if next word contains no spacing
 
                 match (prev_spacing, words_iter.peek()) {
+                    //a comment ending a line after code
+                    (Some(prev), next)
+                        if word.line.trim_start().starts_with("/*")
+                            && prev != SpaceType::NewLine
+                            && prev != SpaceType::LineBreak
+                            && next.is_none_or(|x| {
+                                x.1.spacing_attribute == SpaceType::NewLine
+                                    || x.1.spacing_attribute == SpaceType::LineBreak
+                            }) =>
+                    {
+                        out.push(COMMENT_MARK);
+                        out.push_str(word.line.trim())
+                    }
                     (_, Some(next)) if next.1.spacing_attribute == SpaceType::NoSpace 
                     && word.spacing_attribute != SpaceType::None=> {
                         //println!("lily");
-                        print!(" {}", word.line.trim())
+                        out.push(' ');
+                        out.push_str(word.line.trim());
                     }
                     (Some(prev), _)
                         if prev ==  SpaceType::NoRightPad
                             ||  prev == SpaceType::ExtraIndent
-                            || prev == SpaceType::NoSpace =>
+                            || prev == SpaceType::NoSpace
+                            || prev == SpaceType::NewLine
+                            || prev == SpaceType::LineBreak =>
                     {
                      //   println!("test");
-                        print!("{}", word.line.trim())
+                        out.push_str(word.line.trim())
                     }
                     _ => {
                       //  println!("third");
-                        print!(" {}", word.line.trim())
+                        out.push(' ');
+                        out.push_str(word.line.trim());
                     }
                 }
             }
             SpaceType::TabPadRight => {
-                print!("{}\t", word.line.trim());
+                out.push_str(word.line.trim());
+                out.push('\t');
+            }
+            SpaceType::Padded => match prev_spacing {
+                Some(SpaceType::NewLine) | Some(SpaceType::LineBreak) => {
+                    out.push_str(word.line.trim_end())
+                }
+                _ => {
+                    out.push(' ');
+                    out.push_str(word.line.trim_end());
+                },
+            },
+            SpaceType::LineBreak => {
+                new_line(out, options);
+                out.push_str(&get_tabs(indent_level));
             }
             SpaceType::NoLeftPad => {
-                print!("{}", word.line.trim())
+                out.push_str(word.line.trim())
             }
             SpaceType::NoSpace => {
-                print!("{}", word.line.trim())
+                out.push_str(word.line.trim())
             }
             SpaceType::End => {
                 if words_iter.peek().is_none() {
-                    print!("{} ", word.line.trim());
+                    out.push_str(word.line.trim());
+                    out.push(' ');
                 } else {
-                    print!("{}", word.line.trim())
+                    out.push_str(word.line.trim())
                 }
             }
             SpaceType::NewLine => {
@@ -267,34 +923,150 @@ fn format_statement(statement: Pair<Rule>, indent_level: usize) -> usize {
                     Some((_, peeked_print_info))
                         if peeked_print_info.spacing_attribute == SpaceType::NewLine =>
                     {
-                        println!("")
+                        let blank_lines = line_ending(options).repeat(options.max_blank_lines + 1);
+                        if !out.ends_with(&blank_lines) {
+                            new_line(out, options)
+                        }
                     }
                     Some((_, peeked_print_info))
                         if peeked_print_info.spacing_attribute == SpaceType::End =>
                     {
-                        println!("");
+                        new_line(out, options);
                         if indent_level > 0 {
-                            print!("{}", get_tabs(indent_level))
+                            out.push_str(&get_tabs(indent_level))
                         }
                     }
                     Some((_, peeked_print_info))
                         if peeked_print_info.spacing_attribute == SpaceType::ExtraIndent =>
                     {
-                        println!();
+                        new_line(out, options);
 
-                        print!("{}", get_tabs(indent_level + 2))
+                        out.push_str(&get_tabs(indent_level + 2))
                     }
                     _ => {
-                        println!("");
-                        print!("{}", get_tabs(indent_level + 1))
+                        new_line(out, options);
+                        out.push_str(&get_tabs(indent_level + 1))
                     }
                 }
             }
         }
         prev_spacing = Some(word.spacing_attribute);
     }
+    for _ in 0..trailing_newlines {
+        new_line(out, options);
+    }
+
+    if else_of_hanging && (hanging == Some(true) || !has_if) {
+        blocks[indent_level - 1] = BlockType::Hanging(false);
+    }
+    //an ELSE of a nested IF on its own line lines up with that IF
+    if hanging == Some(true) && !opens_block {
+        blocks.push(BlockType::Hanging(true));
+    }
+
+    closed_block
+}
+
+/**
+    starts the line of a branch, one level past a nested IF that is on a line of its own
+*/
+fn push_branch_line(print_list: &mut Vec<PrintInfo>, hanging: Option<bool>) {
+    print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+    if hanging == Some(true) {
+        print_list.push(PrintInfo::new(String::new(), SpaceType::ExtraIndent));
+    }
+}
+
+/**
+    the PROCEDURE line with its options, along with the procedure name
+*/
+fn format_procedure_header(header: Pair<Rule>) -> (Vec<PrintInfo>, String) {
+    let mut print_list = Vec::new();
+    let mut name = String::new();
+    for iner in header.into_inner() {
+        match iner.as_rule() {
+            Rule::procedure_keyword => {
+                print_list.push(PrintInfo::new("PROCEDURE".to_string(), SpaceType::None))
+            }
+            Rule::define_name => {
+                name = iner.as_str().to_string();
+                print_list.push(PrintInfo::new(name.clone(), SpaceType::None));
+            }
+            Rule::procedure_option => print_list.append(&mut format_words(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected procedure {:?}", une),
+        }
+    }
+    (print_list, name)
+}
+
+/**
+    lines up the comments ending a run of consecutive lines one gap past the longest code,
+    when that would take a line past the line width each comment keeps just the gap
+*/
+fn align_trailing_comments(out: &str, options: &FormatOptions) -> String {
+    let mut aligned = String::with_capacity(out.len());
+    let mut run = Vec::new();
+
+    for line in out.split('\n') {
+        match line.split_once(COMMENT_MARK) {
+            Some((code, comment)) => run.push((code.trim_end(), comment)),
+            None => {
+                push_comment_run(&mut aligned, &mut run, options);
+                aligned.push_str(line);
+                aligned.push('\n');
+            }
+        }
+    }
+    push_comment_run(&mut aligned, &mut run, options);
+    //split gives the text after the last newline as a line of its own
+    aligned.pop();
+    aligned
+}
+
+fn push_comment_run(aligned: &mut String, run: &mut Vec<(&str, &str)>, options: &FormatOptions) {
+    let width = |text: &str| -> usize {
+        //the carriage return of a CRLF ending takes no room
+        text.chars()
+            .filter(|x| *x != '\r')
+            .map(|x| if x == '\t' { TAB_WIDTH } else { 1 })
+            .sum()
+    };
+    let column = run.iter().map(|(code, _)| width(code)).max().unwrap_or_default()
+        + options.trailing_comment_gap;
+    let fits = run
+        .iter()
+        .all(|(_, comment)| column + width(comment) <= options.line_width);
+
+    for (code, comment) in run.drain(..) {
+        let gap = if options.align_trailing_comments && fits {
+            column - width(code)
+        } else {
+            options.trailing_comment_gap
+        };
+        aligned.push_str(code);
+        aligned.push_str(&" ".repeat(gap));
+        //a second statement on the line has its comment spaced normally
+        aligned.push_str(&comment.replace(COMMENT_MARK, " "));
+        aligned.push('\n');
+    }
+}
+
+/**
+    ends the line without the spaces or tabs left at the end of it
+*/
+fn new_line(out: &mut String, options: &FormatOptions) {
+    out.truncate(out.trim_end_matches([' ', '\t']).len());
+    out.push_str(line_ending(options));
+}
 
-    next_indent
+fn line_ending(options: &FormatOptions) -> &'static str {
+    match options.end_of_line {
+        EndOfLine::Crlf => "\r\n",
+        //Auto is settled on one of the others before anything is written
+        EndOfLine::Lf | EndOfLine::Auto => "\n",
+    }
 }
 
 fn get_tabs(indent_level: usize) -> String {
@@ -305,6 +1077,16 @@ fn get_tabs(indent_level: usize) -> String {
     tabs
 }
 
+/**
+    keeps a comment on the line it ends instead of joining the next line onto it
+*/
+fn push_comment_break(print_list: &mut Vec<PrintInfo>, white_space: Pair<Rule>) {
+    let after_comment = print_list.last().is_some_and(|x| x.line.starts_with("/*"));
+    if after_comment {
+        print_list.extend(format_whitespace(white_space));
+    }
+}
+
 fn format_whitespace(white_space: Pair<Rule>) -> Option<PrintInfo> {
     match white_space.clone().into_inner().next() {
         Some(iner) => match iner.as_rule() {
@@ -369,8 +1151,168 @@ fn format_properties(properties:Pair<Rule>) -> Vec<PrintInfo>{
                     SpaceType::None,
                 ))
             }
+            Rule::member => print_list.append(&mut format_member(iner)),
+            Rule::call_arguments => print_list.append(&mut format_call_arguments(iner)),
+            Rule::indexer => print_list.append(&mut format_indexer(iner)),
             _ => panic!("unhandled property")
         }
     }
     print_list
-}
\ No newline at end of file
+}
+
+/** a member after the colon is a name of the class, so it is kept as written even when it reads as a keyword */
+fn format_member(member: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    for iner in member.into_inner() {
+        match iner.as_rule() {
+            Rule::keyword | Rule::variable => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::call_arguments => print_list.append(&mut format_call_arguments(iner)),
+            Rule::indexer => print_list.append(&mut format_indexer(iner)),
+            une => panic!("unexpected member {:?}", une),
+        }
+    }
+    print_list
+}
+
+fn format_indexer(indexer: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    for iner in indexer.into_inner() {
+        match iner.as_rule() {
+            Rule::left_square_bracket => {
+                print_list.push(PrintInfo::new("[".to_string(), SpaceType::NoSpace))
+            }
+            Rule::right_square_bracket => {
+                print_list.push(PrintInfo::new("]".to_string(), SpaceType::NoLeftPad))
+            }
+            Rule::expression => print_list.append(&mut format_expression(iner, false)),
+            Rule::WHITESPACE => {}
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            une => panic!("unexpected indexer {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    joins a print list into a single line, used where a construct is laid out in columns
+    rather than through the statement printer
+*/
+fn print_list_to_line(print_list: Vec<PrintInfo>) -> String {
+    let mut line = String::new();
+    let mut prev_spacing = None;
+    for word in print_list.iter() {
+        if word.spacing_attribute == SpaceType::NewLine
+            || word.spacing_attribute == SpaceType::LineBreak
+        {
+            continue;
+        }
+        if !is_glued(prev_spacing, &word.spacing_attribute) {
+            line.push(' ');
+        }
+        line.push_str(word.line.trim());
+        prev_spacing = Some(&word.spacing_attribute);
+    }
+    line
+}
+
+/**
+    whether the words fit on one line at the indent, leaving room for the statement end
+*/
+fn fits_line(print_list: Vec<PrintInfo>, indent: usize, options: &FormatOptions) -> bool {
+    indent * TAB_WIDTH + print_list_to_line(print_list).len() < options.line_width
+}
+
+/**
+    whether a word is joined to the previous one without a space
+*/
+fn is_glued(prev_spacing: Option<&SpaceType>, spacing: &SpaceType) -> bool {
+    matches!(
+        (prev_spacing, spacing),
+        (None, _)
+            | (_, SpaceType::NoLeftPad)
+            | (_, SpaceType::NoSpace)
+            | (_, SpaceType::End)
+            | (Some(SpaceType::NoSpace), _)
+            | (Some(SpaceType::NoRightPad), _)
+    )
+}
+
+/**
+    formats the words of a declaration seperated by single spaces, names keep their case
+    and everything else that is not a literal is uppercased
+*/
+fn format_words(words: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    for iner in words.into_inner() {
+        match iner.as_rule() {
+            Rule::variable
+            | Rule::define_name
+            | Rule::record_name
+            | Rule::type_name
+            | Rule::int
+            | Rule::decimal => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::datatype => print_list.append(&mut format_datatype(iner)),
+            Rule::function => print_list.append(&mut format_function(iner)),
+            Rule::properties => print_list.append(&mut format_properties(iner)),
+            Rule::comma | Rule::right_parenthesis => print_list.push(PrintInfo::new(
+                iner.as_str().to_string(),
+                SpaceType::NoLeftPad,
+            )),
+            Rule::left_parenthesis => print_list.push(PrintInfo::new(
+                iner.as_str().to_string(),
+                SpaceType::NoRightPad,
+            )),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => push_comment_break(&mut print_list, iner),
+            _ if iner.clone().into_inner().next().is_some() => {
+                print_list.append(&mut format_words(iner))
+            }
+            _ => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+        }
+    }
+    print_list
+}
+
+fn push_word(line: &mut String, word: &str) {
+    if !line.is_empty() {
+        line.push(' ');
+    }
+    line.push_str(word);
+}
+
+/**
+    pads every column to the widest entry of that column across the rows,
+    columns that are empty in every row are left out entirely
+*/
+fn align_columns(rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows {
+        for (i, column) in row.iter().enumerate() {
+            match widths.get_mut(i) {
+                Some(width) if column.len() > *width => *width = column.len(),
+                Some(_) => {}
+                None => widths.push(column.len()),
+            }
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (width, column) in widths.iter().zip(row.iter()) {
+                if *width > 0 {
+                    line.push_str(&column.pad_to_width(*width));
+                    line.push(' ');
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
@@ -0,0 +1,307 @@
+use crate::{
+    conditional::split_condition,
+    expression::{format_datatype, format_expression},
+    format_comment, format_words, FormatOptions, PrintInfo, Rule, SpaceType,
+};
+use pest::iterators::Pair;
+
+/**
+    the buffers of FOR EACH, OPEN QUERY and PRESELECT, several buffers are put
+    one per line and the sort phrase then gets a line of its own, comments stay
+    where they are along with a line break after them
+*/
+pub fn format_record_phrases(
+    record_phrases: Pair<Rule>,
+    options: &FormatOptions,
+) -> Vec<PrintInfo> {
+    let items = record_phrases.into_inner().collect::<Vec<_>>();
+    let one_per_line = items.iter().any(|x| x.as_rule() == Rule::comma);
+    let mut print_list = Vec::new();
+    let mut phrase_count = 0;
+
+    for (i, iner) in items.iter().cloned().enumerate() {
+        let starts_line = print_list
+            .last()
+            .is_some_and(|x: &PrintInfo| x.spacing_attribute == SpaceType::NewLine);
+        match iner.as_rule() {
+            Rule::record_phrase => {
+                if phrase_count > 0 && !starts_line {
+                    print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                }
+                print_list.append(&mut format_record_phrase(iner, options.split_conditions));
+                phrase_count += 1;
+            }
+            Rule::sort_phrase => {
+                if one_per_line && !starts_line {
+                    print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                }
+                print_list.append(&mut format_sort_phrase(iner));
+            }
+            Rule::COMMENT => {
+                print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None));
+                if breaks_line(&items[i + 1..]) {
+                    print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                }
+            }
+            Rule::comma => print_list.push(PrintInfo::new(",".to_string(), SpaceType::NoLeftPad)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected record phrases {:?}", une),
+        }
+    }
+    while print_list
+        .last()
+        .is_some_and(|x| x.spacing_attribute == SpaceType::NewLine)
+    {
+        print_list.pop();
+    }
+    print_list
+}
+
+/**
+    whether a line break follows before the next part
+*/
+fn breaks_line(rest: &[Pair<Rule>]) -> bool {
+    rest.iter()
+        .take_while(|x| x.as_rule() == Rule::WHITESPACE)
+        .any(|x| x.as_str().contains('\n'))
+}
+
+/**
+    FIND and its buffer
+*/
+pub fn format_find_phrase(find_phrase: Pair<Rule>, options: &FormatOptions) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    for iner in find_phrase.into_inner() {
+        match iner.as_rule() {
+            Rule::find_keyword => {
+                print_list.push(PrintInfo::new("FIND".to_string(), SpaceType::None))
+            }
+            Rule::record_phrase => {
+                print_list.append(&mut format_record_phrase(iner, options.split_conditions))
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected find {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    CAN-FIND is kept on a single line
+*/
+pub fn format_can_find(can_find: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    for iner in can_find.into_inner() {
+        match iner.as_rule() {
+            Rule::can_find_keyword => {
+                print_list.push(PrintInfo::new("CAN-FIND".to_string(), SpaceType::None))
+            }
+            Rule::left_parenthesis => {
+                print_list.push(PrintInfo::new("(".to_string(), SpaceType::NoSpace))
+            }
+            Rule::right_parenthesis => {
+                print_list.push(PrintInfo::new(")".to_string(), SpaceType::NoLeftPad))
+            }
+            Rule::record_phrase => print_list.extend(
+                format_record_phrase(iner, false)
+                    .into_iter()
+                    .filter(|x| x.spacing_attribute != SpaceType::NewLine),
+            ),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected can-find {:?}", une),
+        }
+    }
+    print_list
+}
+
+const HEAD: usize = 0;
+const OF: usize = 1;
+const WHERE: usize = 2;
+const USE_INDEX: usize = 3;
+const LOCK: usize = 4;
+const FLAGS: usize = 5;
+
+/**
+    the options of a buffer are put in the order OF, WHERE, USE-INDEX, lock and then the rest,
+    a comment moves along with the part before it unless it ends the buffer
+*/
+pub fn format_record_phrase(record_phrase: Pair<Rule>, split_conditions: bool) -> Vec<PrintInfo> {
+    let mut parts: [Vec<PrintInfo>; 6] = Default::default();
+    let mut trailing_comments = Vec::new();
+    let mut part = HEAD;
+
+    let items = record_phrase.into_inner().collect::<Vec<_>>();
+    for (i, iner) in items.iter().cloned().enumerate() {
+        let rest = &items[i + 1..];
+        let last = rest.iter().all(|x| x.as_rule() == Rule::WHITESPACE);
+        let mut comments = Vec::new();
+        let mut comment_break = breaks_line(rest);
+        match iner.as_rule() {
+            Rule::record_keyword => parts[HEAD].push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::record_name => {
+                parts[HEAD].push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::record_option => {
+                let option = iner.into_inner().next().expect("empty record option");
+                match option.as_rule() {
+                    Rule::record_of => {
+                        part = OF;
+                        parts[OF].append(&mut format_words(option))
+                    }
+                    Rule::record_where => {
+                        part = WHERE;
+                        //the condition takes in the comments and line break after it
+                        let text = option.as_str();
+                        comment_break |= text[text.trim_end().len()..].contains('\n');
+                        let (mut condition, mut after) =
+                            format_record_where(option, split_conditions);
+                        parts[WHERE].append(&mut condition);
+                        comments.append(&mut after);
+                    }
+                    Rule::record_use_index => {
+                        part = USE_INDEX;
+                        parts[USE_INDEX].append(&mut format_words(option))
+                    }
+                    Rule::lock_keyword => {
+                        part = LOCK;
+                        parts[LOCK].push(PrintInfo::new(
+                            option.as_str().to_uppercase(),
+                            SpaceType::None,
+                        ))
+                    }
+                    _ => {
+                        part = FLAGS;
+                        parts[FLAGS].push(PrintInfo::new(
+                            option.as_str().to_uppercase(),
+                            SpaceType::None,
+                        ))
+                    }
+                }
+            }
+            Rule::COMMENT => comments.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected record phrase {:?}", une),
+        }
+        if comments.is_empty() {
+            continue;
+        }
+        if last {
+            trailing_comments.append(&mut comments);
+        } else {
+            parts[part].append(&mut comments);
+            if comment_break {
+                parts[part].push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+            }
+        }
+    }
+
+    let mut print_list = parts
+        .iter_mut()
+        .flat_map(|x| x.drain(..))
+        .collect::<Vec<_>>();
+    while print_list
+        .last()
+        .is_some_and(|x| x.spacing_attribute == SpaceType::NewLine)
+    {
+        print_list.pop();
+    }
+    print_list.append(&mut trailing_comments);
+    print_list
+}
+
+/**
+    line breaks inside the condition are kept, the ones around it are dropped
+    as the options around it are reordered, when splitting the conditions
+    WHERE starts a line and each AND/OR starts another, the comments ending
+    the condition are returned apart from it
+*/
+fn format_record_where(
+    record_where: Pair<Rule>,
+    split_conditions: bool,
+) -> (Vec<PrintInfo>, Vec<PrintInfo>) {
+    let mut print_list = Vec::new();
+    for iner in record_where.into_inner() {
+        match iner.as_rule() {
+            Rule::where_keyword => {}
+            Rule::expression => print_list.append(&mut format_expression(iner, true)),
+            Rule::keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
+                SpaceType::None,
+            )),
+            Rule::datatype => print_list.append(&mut format_datatype(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {
+                if iner.as_str().contains('\n') {
+                    print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                }
+            }
+            une => panic!("unexpected where {:?}", une),
+        }
+    }
+    let mut comments = Vec::new();
+    while let Some(last) = print_list.pop() {
+        if last.line.starts_with("/*") {
+            comments.insert(0, last);
+        } else if last.spacing_attribute != SpaceType::NewLine {
+            print_list.push(last);
+            break;
+        }
+    }
+
+    let condition = match split_condition("WHERE", &print_list).filter(|_| split_conditions) {
+        Some(lines) => lines
+            .into_iter()
+            .flat_map(|line| {
+                vec![
+                    PrintInfo::new("\n".to_string(), SpaceType::NewLine),
+                    PrintInfo::new(line, SpaceType::Padded),
+                ]
+            })
+            .collect(),
+        None => {
+            print_list.insert(0, PrintInfo::new("WHERE".to_string(), SpaceType::None));
+            print_list
+        }
+    };
+    (condition, comments)
+}
+
+/**
+    BREAK and the BY clauses all go on one line
+*/
+fn format_sort_phrase(sort_phrase: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+    for iner in sort_phrase.into_inner() {
+        match iner.as_rule() {
+            Rule::break_keyword => {
+                print_list.push(PrintInfo::new("BREAK".to_string(), SpaceType::None))
+            }
+            Rule::sort_by => {
+                for part in iner.into_inner() {
+                    match part.as_rule() {
+                        Rule::by_keyword | Rule::descending_keyword => print_list.push(
+                            PrintInfo::new(part.as_str().to_uppercase(), SpaceType::None),
+                        ),
+                        Rule::expression => print_list.extend(
+                            format_expression(part, false)
+                                .into_iter()
+                                .filter(|x| x.spacing_attribute != SpaceType::NewLine),
+                        ),
+                        Rule::COMMENT => {
+                            print_list.push(PrintInfo::new(format_comment(part), SpaceType::None))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            _ => {}
+        }
+    }
+    print_list
+}
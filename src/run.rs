@@ -0,0 +1,121 @@
+use crate::{
+    expression::format_expression, format_comment, format_words, function::format_parameter_rows,
+    print_list_to_line, push_word, PrintInfo, Rule, SpaceType,
+};
+use pest::iterators::Pair;
+
+const MODE: usize = 0;
+const VALUE: usize = 1;
+const OPTIONS: usize = 2;
+
+/**
+    RUN with its target and options, the parameters are laid out like
+    the parameters of a function definition
+*/
+pub fn format_run_statement(run_statement: Pair<Rule>, wrap: bool) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in run_statement.into_inner() {
+        match iner.as_rule() {
+            Rule::run_keyword => {
+                print_list.push(PrintInfo::new("RUN".to_string(), SpaceType::None))
+            }
+            Rule::run_target => print_list.append(&mut format_run_target(iner)),
+            Rule::run_parameters => print_list.append(&mut format_run_parameters(iner, wrap)),
+            Rule::run_option => print_list.append(&mut format_words(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected run {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    the procedure name is kept as written as it may be a case sensitive path
+*/
+fn format_run_target(run_target: Pair<Rule>) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in run_target.into_inner() {
+        match iner.as_rule() {
+            Rule::run_path => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::run_value => {
+                let mut words = Vec::new();
+                for part in iner.into_inner() {
+                    match part.as_rule() {
+                        Rule::value_keyword => {
+                            words.push(PrintInfo::new("VALUE".to_string(), SpaceType::None))
+                        }
+                        Rule::left_parenthesis => {
+                            words.push(PrintInfo::new("(".to_string(), SpaceType::NoSpace))
+                        }
+                        Rule::right_parenthesis => {
+                            words.push(PrintInfo::new(")".to_string(), SpaceType::NoLeftPad))
+                        }
+                        Rule::expression => words.append(&mut format_expression(part, false)),
+                        Rule::COMMENT => {
+                            words.push(PrintInfo::new(format_comment(part), SpaceType::None))
+                        }
+                        Rule::WHITESPACE => {}
+                        une => panic!("unexpected run value {:?}", une),
+                    }
+                }
+                print_list.push(PrintInfo::new(print_list_to_line(words), SpaceType::None));
+            }
+            une => panic!("unexpected run target {:?}", une),
+        }
+    }
+    print_list
+}
+
+fn format_run_parameters(parameters: Pair<Rule>, wrap: bool) -> Vec<PrintInfo> {
+    let mut rows = Vec::new();
+    let mut comments: Vec<Vec<String>> = Vec::new();
+
+    for iner in parameters.into_inner() {
+        match iner.as_rule() {
+            Rule::run_parameter => {
+                rows.push(format_run_parameter(iner));
+                comments.push(Vec::new());
+            }
+            //comments stay with the parameter before them
+            Rule::COMMENT => match comments.last_mut() {
+                Some(row_comments) => row_comments.push(format_comment(iner)),
+                None => comments.push(vec![format_comment(iner)]),
+            },
+            Rule::left_parenthesis | Rule::right_parenthesis | Rule::comma | Rule::WHITESPACE => {}
+            une => panic!("unexpected run parameters {:?}", une),
+        }
+    }
+    format_parameter_rows(rows, comments, wrap)
+}
+
+fn format_run_parameter(parameter: Pair<Rule>) -> Vec<String> {
+    let mut columns = vec![String::new(); 3];
+
+    for iner in parameter.into_inner() {
+        match iner.as_rule() {
+            Rule::parameter_mode => columns[MODE] = iner.as_str().to_uppercase(),
+            Rule::parameter_table => {
+                push_word(&mut columns[VALUE], &print_list_to_line(format_words(iner)))
+            }
+            Rule::buffer_keyword => push_word(&mut columns[VALUE], &iner.as_str().to_uppercase()),
+            Rule::record_name => push_word(&mut columns[VALUE], iner.as_str()),
+            Rule::expression => push_word(
+                &mut columns[VALUE],
+                &print_list_to_line(format_expression(iner, false)),
+            ),
+            Rule::parameter_option => push_word(
+                &mut columns[OPTIONS],
+                &print_list_to_line(format_words(iner)),
+            ),
+            Rule::COMMENT => push_word(&mut columns[OPTIONS], &format_comment(iner)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected run parameter {:?}", une),
+        }
+    }
+    columns
+}
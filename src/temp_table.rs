@@ -1,164 +1,334 @@
-use crate::{format_comment, format_whitespace, PrintInfo, Rule, SpaceType};
-use pad::PadStr;
-use pest::iterators::{Pair, Pairs};
-
-struct FieldInfo {
-    before_variable: String,
-    variable: String,
-    variable_length: usize,
-    after_variable: String,
+use crate::{
+    align_columns, expression::format_datatype, format_comment, format_words,
+    function::format_function, print_list_to_line, push_word, PrintInfo, Rule, SpaceType,
+    COMMENT_MARK,
+};
+use pest::iterators::Pair;
+
+/**
+    the columns of a single FIELD or INDEX line, in the order they are printed
+*/
+struct TableColumns {
+    columns: Vec<String>,
+    trailing_comment: Option<String>,
 }
 
-impl FieldInfo {
-    fn new(
-        before_variable: String,
-        vairable: String,
-        variable_length: usize,
-        after_variable: String,
-    ) -> Self {
-        Self {
-            before_variable,
-            variable: vairable,
-            variable_length,
-            after_variable,
-        }
-    }
+enum TableLine {
+    Row(TableColumns),
+    Comment(String),
+    Blank,
 }
 
+const FIELD: usize = 0;
+const NAME: usize = 1;
+const TYPE: usize = 2;
+const EXTENT: usize = 3;
+const INITIAL: usize = 4;
+const FORMAT: usize = 5;
+const LABEL: usize = 6;
+const SERIALIZE_NAME: usize = 7;
+const XML_NODE_TYPE: usize = 8;
+const OTHER: usize = 9;
+
+const INDEX: usize = 0;
+const INDEX_NAME: usize = 1;
+const INDEX_OPTIONS: usize = 2;
+const INDEX_COMPONENTS: usize = 3;
+
 pub fn format_temp_table(temp_table: Pair<Rule>) -> Vec<PrintInfo> {
     let mut print_list = Vec::new();
+    let mut options = Vec::new();
+    let mut like = Vec::new();
+    let mut no_undo = None;
 
+    //fields and indexes put themselves on their own lines so the source layout is not kept
     for iner in temp_table.into_inner() {
         match iner.as_rule() {
-            Rule::define_keyword | Rule::keyword | Rule::temptable_keyword => {
-                print_list.push(PrintInfo::new(
-                    format!("{}", iner.as_span().as_str().to_uppercase()),
-                    SpaceType::None,
-                ))
-            }
-            Rule::variable => print_list.push(PrintInfo::new(
-                format!("{}", iner.as_span().as_str()),
+            Rule::define_keyword
+            | Rule::define_modifier
+            | Rule::keyword
+            | Rule::temptable_keyword => print_list.push(PrintInfo::new(
+                iner.as_str().to_uppercase(),
                 SpaceType::None,
             )),
-            Rule::temp_table_like => print_list.append(&mut format_temp_table_like(iner)),
-            Rule::temp_table_fields => print_list.append(&mut format_temp_table_fields(iner)),
-            Rule::WHITESPACE | Rule::NEWLINE => {
-                if let Some(f) = format_whitespace(iner) {
-                    print_list.push(f);
+            Rule::define_name => {
+                print_list.push(PrintInfo::new(iner.as_str().to_string(), SpaceType::None))
+            }
+            Rule::temp_table_option => match iner.clone().into_inner().next() {
+                Some(x) if x.as_rule() == Rule::noundo_keyword => {
+                    no_undo = Some(PrintInfo::new(x.as_str().to_uppercase(), SpaceType::None))
                 }
+                _ => options.append(&mut format_words(iner)),
+            },
+            Rule::temp_table_like => {
+                like.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                like.append(&mut format_words(iner));
             }
-            _ => eprintln!("@@@temp-table-not handled"),
+            Rule::temp_table_fields => {
+                //NO-UNDO leads the options so tables read the same way
+                print_list.extend(no_undo.take());
+                print_list.append(&mut options);
+                print_list.append(&mut like);
+                print_list.append(&mut format_temp_table_lines(
+                    iner,
+                    format_temp_table_single_field,
+                ))
+            }
+            Rule::temp_table_indexes => {
+                print_list.append(&mut format_temp_table_lines(iner, format_temp_table_index))
+            }
+            Rule::WHITESPACE | Rule::NEWLINE => {}
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            une => panic!("unexpected temp-table {:?}", une),
         }
     }
     print_list
 }
 
-fn format_temp_table_like(like_field: Pair<Rule>) -> Vec<PrintInfo> {
+/**
+    fields and indexes of an include file, the lines start at the
+    indent of the include rather than one level in from a DEFINE
+*/
+pub fn format_temp_table_fragment(fragment: Pair<Rule>) -> Vec<PrintInfo> {
     let mut print_list = Vec::new();
 
-    for iner in like_field.into_inner() {
+    for iner in fragment.into_inner() {
         match iner.as_rule() {
-            Rule::like_keyword | Rule::keyword => print_list.push(PrintInfo::new(
-                format!("{}", iner.as_span().as_str().to_uppercase()),
-                SpaceType::None,
+            Rule::temp_table_fields => print_list.append(&mut format_temp_table_lines(
+                iner,
+                format_temp_table_single_field,
             )),
-            Rule::variable => print_list.push(PrintInfo::new(
-                format!("{}", iner.as_span().as_str()),
-                SpaceType::None,
-            )),
-            Rule::WHITESPACE | Rule::NEWLINE => {
-                if let Some(f) = format_whitespace(iner) {
-                    print_list.push(f);
-                }
+            Rule::temp_table_indexes => {
+                print_list.append(&mut format_temp_table_lines(iner, format_temp_table_index))
             }
-            _ => panic!("temp table like not formatted"),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected temp-table fragment {:?}", une),
         }
     }
     print_list
+        .into_iter()
+        .skip(1)
+        .map(|word| match word.spacing_attribute {
+            SpaceType::NewLine => PrintInfo::new(word.line, SpaceType::LineBreak),
+            _ => word,
+        })
+        .collect()
 }
 
-fn format_temp_table_fields(fields: Pair<Rule>) -> Vec<PrintInfo> {
-    let mut fields_and_length_list = Vec::new();
-    let mut largest_variable_length = 0;
-    for iner in fields.into_inner() {
-        let next_field = format_temp_table_single_field(iner);
-        if next_field.variable_length > largest_variable_length {
-            largest_variable_length = next_field.variable_length;
+/**
+    lays out either the fields or the indexes, one per line with their columns aligned
+*/
+fn format_temp_table_lines(
+    table_lines: Pair<Rule>,
+    format_row: fn(Pair<Rule>, &mut Vec<TableLine>, &mut usize),
+) -> Vec<PrintInfo> {
+    let mut lines = Vec::new();
+    let mut newlines = 0;
+
+    for iner in table_lines.into_inner() {
+        match iner.as_rule() {
+            Rule::COMMENT => push_comment(&mut lines, format_comment(iner), &mut newlines),
+            Rule::WHITESPACE => {
+                if iner.as_str().contains('\n') {
+                    newlines += 1;
+                }
+            }
+            _ => {
+                push_blank_lines(&mut lines, newlines);
+                newlines = 0;
+                format_row(iner, &mut lines, &mut newlines);
+            }
+        }
+    }
+
+    let rows = lines
+        .iter()
+        .filter_map(|line| match line {
+            TableLine::Row(row) => Some(row.columns.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut aligned_rows = align_columns(&rows).into_iter();
+
+    let mut print_list = Vec::with_capacity(lines.len() * 2);
+    for line in lines {
+        print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+        match line {
+            TableLine::Row(row) => {
+                let mut aligned = aligned_rows.next().unwrap_or_default();
+                if let Some(comment) = row.trailing_comment {
+                    aligned.push(COMMENT_MARK);
+                    aligned.push_str(&comment);
+                }
+                print_list.push(PrintInfo::new(aligned, SpaceType::None));
+            }
+            TableLine::Comment(comment) => {
+                print_list.push(PrintInfo::new(comment, SpaceType::None))
+            }
+            TableLine::Blank => {}
         }
-        fields_and_length_list.push(next_field);
     }
-    let print_list_alloc_len = fields_and_length_list.len();
-    let print_list = fields_and_length_list.into_iter().fold(
-        Vec::with_capacity(print_list_alloc_len),
-        |mut acc, mut curr| -> Vec<PrintInfo> {
-            let mut line = String::new();
-            /*line.push_str(&format!(" "));*/
-            line.push_str(&mut curr.before_variable);
-            line.push_str(&format!(" "));
-            line.push_str(&mut curr.variable.pad_to_width(largest_variable_length));
-            // line.push_str(&format!(" "));
-            line.push_str(&mut curr.after_variable);
-
-            acc.push(PrintInfo::new(line, SpaceType::None));
-            acc.push(PrintInfo::new(format!("\n"), SpaceType::NewLine));
-            acc
-        },
-    );
     print_list
 }
 
-fn format_temp_table_single_field(single_field: Pair<Rule>) -> FieldInfo {
-    //println!("$$$Single FIELD{:?}$$$$$", single_field);
-    let mut field_iterator = single_field.into_inner();
-    loop {
-        /*let next = match field_iterator.next() {
-            Some(x) => x,
-            None => break,
-        };*/
-        let (before_variable, variable) =
-            format_temp_table_single_field_process_to_variable(&mut field_iterator);
-        let after_variable = format_temp_table_single_filed_to_end(&mut field_iterator);
-        //println!("####{}###", after_variable);
-        let variable_len = variable.len();
-        break FieldInfo::new(before_variable, variable, variable_len, after_variable);
+fn push_blank_lines(lines: &mut Vec<TableLine>, newlines: usize) {
+    if lines.is_empty() {
+        return;
+    }
+    for _ in 1..newlines {
+        lines.push(TableLine::Blank);
     }
 }
 
-fn format_temp_table_single_field_process_to_variable(
-    field_iterator: &mut Pairs<Rule>,
-) -> (String, String) {
-    let mut before_variable_string = String::new();
-    let variable = loop {
-        let next = match field_iterator.next() {
-            Some(x) => x,
-            None => panic!("invalid pass through temp-table"),
-        };
-
-        match next.as_rule() {
-            Rule::field_keyword | Rule::aslike_keyword | Rule::keyword => {
-                before_variable_string.push_str(&next.as_str().to_uppercase())
-            }
-            Rule::variable => break next.as_str().to_string(),
-            Rule::WHITESPACE | Rule::NEWLINE => {
-                if let Some(f) = format_whitespace(next) {
-                    before_variable_string.push_str(&f.line);
+/**
+    comments on the same line as a field stay with that field,
+    otherwise they get a line of their own
+*/
+fn push_comment(lines: &mut Vec<TableLine>, comment: String, newlines: &mut usize) {
+    match lines.last_mut() {
+        Some(TableLine::Row(field)) if *newlines == 0 => match field.trailing_comment {
+            Some(ref mut trailing) => {
+                trailing.push(' ');
+                trailing.push_str(&comment);
+            }
+            None => field.trailing_comment = Some(comment),
+        },
+        _ => {
+            push_blank_lines(lines, *newlines);
+            lines.push(TableLine::Comment(comment));
+            *newlines = 0;
+        }
+    }
+}
+
+fn format_temp_table_single_field(
+    single_field: Pair<Rule>,
+    lines: &mut Vec<TableLine>,
+    newlines: &mut usize,
+) {
+    let mut columns = vec![String::new(); 10];
+    let mut other = Vec::new();
+    let mut comments = Vec::new();
+    let mut field_newlines = 0;
+
+    for iner in single_field.into_inner() {
+        match iner.as_rule() {
+            Rule::COMMENT => {
+                comments.push((field_newlines, format_comment(iner)));
+                field_newlines = 0;
+                continue;
+            }
+            Rule::WHITESPACE => {
+                if iner.as_str().contains('\n') {
+                    field_newlines += 1;
                 }
+                continue;
             }
-            Rule::COMMENT => before_variable_string.push_str(&format_comment(next)),
-            _ => panic!("unexpected format to variable"),
+            _ => field_newlines = 0,
         }
-    };
-    (before_variable_string, variable)
+        match iner.as_rule() {
+            Rule::field_keyword => columns[FIELD] = iner.as_str().to_uppercase(),
+            Rule::define_name => columns[NAME] = iner.as_str().to_string(),
+            Rule::define_type => columns[TYPE] = print_list_to_line(format_words(iner)),
+            Rule::temp_table_field_option => {
+                let option = iner
+                    .into_inner()
+                    .next()
+                    .expect("empty temp-table field option");
+                match option.as_rule() {
+                    Rule::temp_table_extent => {
+                        columns[EXTENT] = print_list_to_line(format_words(option))
+                    }
+                    Rule::temp_table_column => {
+                        let column = match option
+                            .clone()
+                            .into_inner()
+                            .next()
+                            .map(|x| x.as_str().to_uppercase())
+                        {
+                            Some(ref x) if x.starts_with("INIT") => INITIAL,
+                            Some(ref x) if x == "FORMAT" => FORMAT,
+                            Some(ref x) if x == "LABEL" => LABEL,
+                            Some(ref x) if x == "SERIALIZE-NAME" => SERIALIZE_NAME,
+                            _ => XML_NODE_TYPE,
+                        };
+                        columns[column] = print_list_to_line(format_words(option));
+                    }
+                    Rule::comma => other.push(PrintInfo::new(
+                        option.as_str().to_string(),
+                        SpaceType::NoLeftPad,
+                    )),
+                    Rule::keyword | Rule::decimal => other.push(PrintInfo::new(
+                        option.as_str().to_uppercase(),
+                        SpaceType::None,
+                    )),
+                    Rule::variable => {
+                        other.push(PrintInfo::new(option.as_str().to_string(), SpaceType::None))
+                    }
+                    Rule::datatype => other.append(&mut format_datatype(option)),
+                    Rule::function => other.append(&mut format_function(option)),
+                    une => panic!("unexpected temp-table field option {:?}", une),
+                }
+            }
+            une => panic!("unexpected temp-table field {:?}", une),
+        }
+    }
+    columns[OTHER] = print_list_to_line(other);
+
+    push_row(lines, columns, comments);
+    *newlines = field_newlines;
 }
 
-fn format_temp_table_single_filed_to_end(field_iterator: &mut Pairs<Rule>) -> String {
-    field_iterator.fold(String::new(), |mut accum, curr| {
-        match curr.as_rule() {
-            Rule::keyword => accum.push_str(&curr.as_str().to_uppercase()),
-            _ => {
-                accum.push_str(curr.as_str());
+fn format_temp_table_index(index: Pair<Rule>, lines: &mut Vec<TableLine>, newlines: &mut usize) {
+    let mut columns = vec![String::new(); 4];
+    let mut comments = Vec::new();
+    let mut index_newlines = 0;
+
+    for iner in index.into_inner() {
+        match iner.as_rule() {
+            Rule::COMMENT => {
+                comments.push((index_newlines, format_comment(iner)));
+                index_newlines = 0;
+            }
+            Rule::WHITESPACE => {
+                if iner.as_str().contains('\n') {
+                    index_newlines += 1;
+                }
+            }
+            Rule::index_keyword => {
+                index_newlines = 0;
+                columns[INDEX] = iner.as_str().to_uppercase()
+            }
+            Rule::define_name => {
+                index_newlines = 0;
+                columns[INDEX_NAME] = iner.as_str().to_string()
             }
+            Rule::index_option => {
+                index_newlines = 0;
+                push_word(&mut columns[INDEX_OPTIONS], &iner.as_str().to_uppercase())
+            }
+            Rule::index_component => {
+                index_newlines = 0;
+                push_word(
+                    &mut columns[INDEX_COMPONENTS],
+                    &print_list_to_line(format_words(iner)),
+                )
+            }
+            une => panic!("unexpected temp-table index {:?}", une),
         }
-        accum
-    })
+    }
+
+    push_row(lines, columns, comments);
+    *newlines = index_newlines;
+}
+
+fn push_row(lines: &mut Vec<TableLine>, columns: Vec<String>, comments: Vec<(usize, String)>) {
+    lines.push(TableLine::Row(TableColumns {
+        columns,
+        trailing_comment: None,
+    }));
+    for (mut comment_newlines, comment) in comments {
+        push_comment(lines, comment, &mut comment_newlines);
+    }
 }
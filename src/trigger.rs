@@ -0,0 +1,88 @@
+use crate::{
+    format_comment, format_words, print_list_to_line, push_word, PrintInfo, Rule, SpaceType,
+};
+use pest::iterators::Pair;
+
+/**
+    ON with its events, widgets and options up to the trigger itself
+*/
+pub fn format_on_trigger(on_trigger: Pair<Rule>, split_events: bool) -> Vec<PrintInfo> {
+    let mut print_list = vec![PrintInfo::new("ON".to_string(), SpaceType::None)];
+
+    for iner in on_trigger.into_inner() {
+        match iner.as_rule() {
+            Rule::trigger_event => print_list.append(&mut format_trigger_event(iner, split_events)),
+            Rule::trigger_or => print_list.push(PrintInfo::new("OR".to_string(), SpaceType::None)),
+            Rule::trigger_option => print_list.append(&mut format_words(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::on_keyword | Rule::WHITESPACE => {}
+            une => panic!("unexpected on trigger {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    WAIT-FOR with its events, widgets and options
+*/
+pub fn format_wait_for(wait_for: Pair<Rule>, split_events: bool) -> Vec<PrintInfo> {
+    let mut print_list = vec![PrintInfo::new("WAIT-FOR".to_string(), SpaceType::None)];
+
+    for iner in wait_for.into_inner() {
+        match iner.as_rule() {
+            Rule::trigger_event => print_list.append(&mut format_trigger_event(iner, split_events)),
+            Rule::trigger_or => print_list.push(PrintInfo::new("OR".to_string(), SpaceType::None)),
+            Rule::wait_for_option => print_list.append(&mut format_words(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::wait_for_keyword | Rule::WHITESPACE => {}
+            une => panic!("unexpected wait-for {:?}", une),
+        }
+    }
+    print_list
+}
+
+/**
+    the events and what they apply to, when splitting each event after the
+    first starts a line and the widgets follow the last one
+*/
+fn format_trigger_event(trigger_event: Pair<Rule>, split_events: bool) -> Vec<PrintInfo> {
+    let mut print_list = Vec::new();
+
+    for iner in trigger_event.into_inner() {
+        match iner.as_rule() {
+            Rule::event_list => {
+                let mut events = Vec::new();
+                for part in iner.into_inner() {
+                    match part.as_rule() {
+                        Rule::event_name => events.push(print_list_to_line(format_words(part))),
+                        Rule::COMMENT => match events.last_mut() {
+                            Some(event) => push_word(event, &format_comment(part)),
+                            None => events.push(format_comment(part)),
+                        },
+                        Rule::comma | Rule::WHITESPACE => {}
+                        une => panic!("unexpected event list {:?}", une),
+                    }
+                }
+                let last = events.len().saturating_sub(1);
+                for (i, event) in events.into_iter().enumerate() {
+                    if i > 0 && split_events {
+                        print_list.push(PrintInfo::new("\n".to_string(), SpaceType::NewLine));
+                    }
+                    print_list.push(PrintInfo::new(event, SpaceType::None));
+                    if i != last {
+                        print_list.push(PrintInfo::new(",".to_string(), SpaceType::NoLeftPad));
+                    }
+                }
+            }
+            Rule::of_keyword => print_list.push(PrintInfo::new("OF".to_string(), SpaceType::None)),
+            Rule::anywhere_keyword => {
+                print_list.push(PrintInfo::new("ANYWHERE".to_string(), SpaceType::None))
+            }
+            Rule::widget_list => print_list.append(&mut format_words(iner)),
+            Rule::COMMENT => print_list.push(PrintInfo::new(format_comment(iner), SpaceType::None)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected trigger event {:?}", une),
+        }
+    }
+    print_list
+}
@@ -0,0 +1,143 @@
+use crate::{format_comment, push_word, Rule};
+use pest::iterators::Pair;
+
+struct Using {
+    line: String,
+    key: String, //the statement without comments, for sorting and spotting repeats
+    comments_before: Vec<String>,
+}
+
+enum UsingLine {
+    Using(Using),
+    Comment(String),
+    Blank,
+}
+
+/**
+    formats a run of consecutive USING statements, when sorting each group of
+    lines between blank lines is ordered by name and repeated names are dropped,
+    returns the lines without indentation and empty lines are blank lines
+*/
+pub fn format_using_statements(usings: Pair<Rule>, sort: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut newlines = 0;
+
+    for iner in usings.into_inner() {
+        match iner.as_rule() {
+            Rule::using_statement => {
+                push_blank_lines(&mut lines, newlines);
+                let line = format_using_statement(iner);
+                lines.push(UsingLine::Using(Using {
+                    key: line.to_lowercase(),
+                    line,
+                    comments_before: Vec::new(),
+                }));
+                newlines = 0;
+            }
+            Rule::COMMENT => {
+                let comment = format_comment(iner);
+                match lines.last_mut() {
+                    Some(UsingLine::Using(using)) if newlines == 0 => {
+                        push_word(&mut using.line, &comment)
+                    }
+                    _ => {
+                        push_blank_lines(&mut lines, newlines);
+                        lines.push(UsingLine::Comment(comment));
+                        newlines = 0;
+                    }
+                }
+            }
+            Rule::WHITESPACE => {
+                if iner.as_str().contains('\n') {
+                    newlines += 1;
+                }
+            }
+            une => panic!("unexpected using {:?}", une),
+        }
+    }
+
+    //newlines consumed after the last using still have to be printed
+    for _ in 0..newlines {
+        lines.push(UsingLine::Blank);
+    }
+
+    let mut formatted = Vec::with_capacity(lines.len());
+    let mut group = Vec::new();
+    for line in lines {
+        match line {
+            UsingLine::Blank => {
+                formatted.append(&mut format_using_group(group, sort));
+                formatted.push(String::new());
+                group = Vec::new();
+            }
+            line => group.push(line),
+        }
+    }
+    formatted.append(&mut format_using_group(group, sort));
+    formatted
+}
+
+fn format_using_group(group: Vec<UsingLine>, sort: bool) -> Vec<String> {
+    //comments on their own line move with the using below them
+    let mut usings = Vec::new();
+    let mut comments = Vec::new();
+    for line in group {
+        match line {
+            UsingLine::Using(mut using) => {
+                using.comments_before = std::mem::take(&mut comments);
+                usings.push(using);
+            }
+            UsingLine::Comment(comment) => comments.push(comment),
+            UsingLine::Blank => {}
+        }
+    }
+
+    if sort {
+        usings.sort_by(|a, b| a.key.cmp(&b.key));
+        //a repeat is only dropped when no comment would be lost with it
+        let mut seen: Vec<String> = Vec::new();
+        usings.retain(|using| {
+            let commented = !using.comments_before.is_empty() || using.line.len() > using.key.len();
+            if seen.contains(&using.key) && !commented {
+                false
+            } else {
+                seen.push(using.key.clone());
+                true
+            }
+        });
+    }
+
+    let mut lines = Vec::new();
+    for using in usings {
+        lines.extend(using.comments_before);
+        lines.push(using.line);
+    }
+    lines.append(&mut comments);
+    lines
+}
+
+fn push_blank_lines(lines: &mut Vec<UsingLine>, newlines: usize) {
+    if lines.is_empty() {
+        return;
+    }
+    for _ in 1..newlines {
+        lines.push(UsingLine::Blank);
+    }
+}
+
+fn format_using_statement(using: Pair<Rule>) -> String {
+    let mut line = String::new();
+    for iner in using.into_inner() {
+        match iner.as_rule() {
+            Rule::using_keyword | Rule::from_keyword | Rule::using_source => {
+                push_word(&mut line, &iner.as_str().to_uppercase())
+            }
+            Rule::using_name => push_word(&mut line, iner.as_str()),
+            Rule::statement_end => line.push('.'),
+            Rule::COMMENT => push_word(&mut line, &format_comment(iner)),
+            Rule::WHITESPACE => {}
+            une => panic!("unexpected using {:?}", une),
+        }
+    }
+    line
+}
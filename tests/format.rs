@@ -0,0 +1,1106 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/**
+    runs the formatter over the source the way it is run from the command line
+*/
+fn format(input: &[u8], options: &[&str]) -> Output {
+    let mut formatter = Command::new(env!("CARGO_BIN_EXE_main"))
+        .args(options)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("formatter did not start");
+    formatter.stdin.take().unwrap().write_all(input).unwrap();
+    formatter.wait_with_output().unwrap()
+}
+
+fn check(input: &str, expected: &str, options: &[&str]) {
+    let output = format(input.as_bytes(), options);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+}
+
+#[test]
+fn define_runs_are_aligned_in_columns() {
+    check(
+        "define variable cName as character no-undo.\n\
+         define variable iCount as integer initial 0 no-undo.\n\
+         define input parameter piId as integer no-undo.\n",
+        "DEFINE VARIABLE        cName  AS CHARACTER           NO-UNDO.\n\
+         DEFINE VARIABLE        iCount AS INTEGER   INITIAL 0 NO-UNDO.\n\
+         DEFINE INPUT PARAMETER piId   AS INTEGER             NO-UNDO.\n",
+        &[],
+    );
+}
+
+#[test]
+fn define_run_after_a_file_header_comment() {
+    check(
+        "/*------------------------\n\
+         \x20 File: customer.p\n\
+         ------------------------*/\n\
+         define variable cName as character no-undo.\n\
+         define variable iCount as integer initial 0 no-undo.\n",
+        "/* ------------------------\n\
+         \x20 File: customer.p\n\
+         ------------------------ */\n\
+         DEFINE VARIABLE cName  AS CHARACTER           NO-UNDO.\n\
+         DEFINE VARIABLE iCount AS INTEGER   INITIAL 0 NO-UNDO.\n",
+        &[],
+    );
+}
+
+#[test]
+fn comment_inside_a_define_type() {
+    check(
+        "define variable x as /* c */ character no-undo.\n\
+         define variable iLonger as integer no-undo.\n\
+         define buffer bCust for /* b */ Customer.\n",
+        "DEFINE VARIABLE x       AS /* c */ CHARACTER NO-UNDO.\n\
+         DEFINE VARIABLE iLonger AS INTEGER           NO-UNDO.\n\
+         DEFINE BUFFER   bCust   FOR /* b */ Customer.\n",
+        &[],
+    );
+}
+
+#[test]
+fn temp_table_fields_are_aligned() {
+    check(
+        "define temp-table ttCust no-undo\n\
+         \x20 field custNum as integer initial 0 format \">>>9\" label \"Num\"\n\
+         \x20 field name as character serialize-name \"nm\".\n",
+        "DEFINE TEMP-TABLE ttCust NO-UNDO\n\
+         \tFIELD custNum AS INTEGER   INITIAL 0 FORMAT \">>>9\" LABEL \"Num\"\n\
+         \tFIELD name    AS CHARACTER                                     SERIALIZE-NAME \"nm\".\n",
+        &[],
+    );
+}
+
+#[test]
+fn temp_table_fields_and_indexes_are_aligned() {
+    check(
+        "define temp-table ttCust no-undo\n\
+         \x20 field custNum as integer initial 0 format \">>>9\" label \"Num\"\n\
+         \x20 field name as character serialize-name \"nm\"\n\
+         \x20 index idxMain is primary unique custNum\n\
+         \x20 index idxName name descending.\n",
+        "DEFINE TEMP-TABLE ttCust NO-UNDO\n\
+         \tFIELD custNum AS INTEGER   INITIAL 0 FORMAT \">>>9\" LABEL \"Num\"\n\
+         \tFIELD name    AS CHARACTER                                     SERIALIZE-NAME \"nm\"\n\
+         \tINDEX idxMain IS PRIMARY UNIQUE custNum\n\
+         \tINDEX idxName                   name DESCENDING.\n",
+        &[],
+    );
+}
+
+#[test]
+fn dataset_lists_buffers_and_relations() {
+    check(
+        "define dataset dsOrder for ttOrder, ttLine data-relation drOL for ttOrder, ttLine relation-fields (OrderNum, OrderNum, LineNum, LineNum) nested.\n",
+        "DEFINE DATASET dsOrder FOR\n\
+         \tttOrder,\n\
+         \tttLine\n\
+         \tDATA-RELATION drOL FOR ttOrder, ttLine RELATION-FIELDS (OrderNum, OrderNum, LineNum, LineNum) NESTED.\n",
+        &[],
+    );
+}
+
+#[test]
+fn dataset_comments_kept_with_what_they_follow() {
+    check(
+        "define dataset dsOrder for ttA /* a */, ttB\n\
+         \x20 data-relation r1 for ttA, ttB relation-fields (x /* key */, x).\n\
+         define data-source srcCust for Customer /* c */ keys (CustNum), Order.\n",
+        "DEFINE DATASET dsOrder FOR\n\
+         \tttA /* a */,\n\
+         \tttB\n\
+         \tDATA-RELATION r1 FOR ttA, ttB RELATION-FIELDS (x /* key */, x).\n\
+         DEFINE DATA-SOURCE srcCust FOR\n\
+         \tCustomer /* c */ KEYS (CustNum),\n\
+         \tOrder.\n",
+        &[],
+    );
+}
+
+#[test]
+fn query_and_frame_definitions() {
+    check(
+        "define query qCust for Customer scrolling.\n\
+         define frame fMain cName at row 1 col 2 view-as fill-in label \"Name\" iNum at row 2 col 2 with side-labels.\n",
+        "DEFINE QUERY qCust FOR Customer SCROLLING.\n\
+         DEFINE FRAME fMain\n\
+         \tcName AT ROW 1 COL 2 VIEW-AS FILL-IN LABEL \"Name\"\n\
+         \tiNum  AT ROW 2 COL 2\n\
+         \tWITH SIDE-LABELS.\n",
+        &[],
+    );
+}
+
+#[test]
+fn label_and_column_label_both_kept() {
+    check(
+        "display cCity label \"City\" column-label \"C\".\n\
+         define frame f\n\
+         \x20 cCity label \"City\" column-label \"C\" format \"x(20)\"\n\
+         \x20 iCount.\n\
+         update cCity label \"City\" column-label \"C\" with frame f.\n",
+        "DISPLAY cCity LABEL \"City\" COLUMN-LABEL \"C\".\n\
+         DEFINE FRAME f\n\
+         \tcCity  LABEL \"City\" COLUMN-LABEL \"C\" FORMAT \"x(20)\"\n\
+         \tiCount.\n\
+         UPDATE cCity LABEL \"City\" COLUMN-LABEL \"C\" WITH FRAME f.\n",
+        &[],
+    );
+}
+
+#[test]
+fn procedure_blocks() {
+    check(
+        "procedure doIt private:\n\
+         \x20 define input parameter piId as integer no-undo.\n\
+         \x20 define variable c as character no-undo.\n\
+         \x20 c = \"a\".\n\
+         end.\n\
+         procedure other:\n\
+         end procedure.\n",
+        "PROCEDURE doIt PRIVATE:\n\
+         \tDEFINE INPUT PARAMETER piId AS INTEGER NO-UNDO.\n\
+         \n\
+         \tDEFINE VARIABLE c AS CHARACTER NO-UNDO.\n\
+         \tc = \"a\".\n\
+         END PROCEDURE.\n\
+         \n\
+         PROCEDURE other:\n\
+         END PROCEDURE.\n",
+        &[],
+    );
+}
+
+#[test]
+fn procedure_end_comment() {
+    check(
+        "procedure doIt:\n\
+         end.\n",
+        "PROCEDURE doIt:\n\
+         END PROCEDURE. /* doIt */\n",
+        &["--procedure-end-comment"],
+    );
+}
+
+#[test]
+fn procedure_blank_lines() {
+    check(
+        "iA = 1.\n\
+         procedure doIt:\n\
+         end.\n\
+         iB = 2.\n",
+        "iA = 1.\n\
+         \n\
+         \n\
+         PROCEDURE doIt:\n\
+         END PROCEDURE.\n\
+         \n\
+         \n\
+         iB = 2.\n",
+        &["--procedure-blank-lines", "2"],
+    );
+}
+
+#[test]
+fn comments_before_a_procedure_kept_with_it() {
+    check(
+        "iA = 1.\n\
+         /* Purpose */\n\
+         procedure p1:\n\
+         end.\n\
+         /* Purpose two */\n\
+         /* more */\n\
+         procedure p2:\n\
+         end.\n",
+        "iA = 1.\n\
+         \n\
+         /* Purpose */\n\
+         PROCEDURE p1:\n\
+         END PROCEDURE.\n\
+         \n\
+         /* Purpose two */\n\
+         /* more */\n\
+         PROCEDURE p2:\n\
+         END PROCEDURE.\n",
+        &[],
+    );
+}
+
+#[test]
+fn blank_lines_after_methods_and_destructors() {
+    check(
+        "class B:\n\
+         \x20 method public void m():\n\
+         \x20 end method.\n\
+         \x20 destructor public B():\n\
+         \x20 end destructor.\n\
+         \x20 iA = 1.\n\
+         \x20 method public void n():\n\
+         \x20 end method.\n\
+         end class.\n",
+        "CLASS B:\n\
+         \n\
+         \tMETHOD PUBLIC VOID m():\n\
+         \tEND METHOD.\n\
+         \n\
+         \tDESTRUCTOR PUBLIC B():\n\
+         \tEND DESTRUCTOR.\n\
+         \n\
+         \tiA = 1.\n\
+         \n\
+         \tMETHOD PUBLIC VOID n():\n\
+         \tEND METHOD.\n\
+         END CLASS.\n",
+        &[],
+    );
+}
+
+#[test]
+fn parameters_kept_apart_from_locals_in_procedures() {
+    check(
+        "procedure doIt:\n\
+         \x20 define input parameter piId as integer no-undo.\n\
+         \x20 define output parameter pcName as character no-undo.\n\
+         \x20 /* locals */\n\
+         \x20 define variable iCount as integer no-undo.\n\
+         end.\n",
+        "PROCEDURE doIt:\n\
+         \tDEFINE INPUT PARAMETER  piId   AS INTEGER   NO-UNDO.\n\
+         \tDEFINE OUTPUT PARAMETER pcName AS CHARACTER NO-UNDO.\n\
+         \n\
+         \t/* locals */\n\
+         \tDEFINE VARIABLE iCount AS INTEGER NO-UNDO.\n\
+         END PROCEDURE.\n",
+        &[],
+    );
+}
+
+#[test]
+fn defines_outside_procedures_not_split() {
+    check(
+        "define variable iCount as integer no-undo. /* c */\n\
+         define input parameter piId as integer no-undo.\n\
+         define variable cName as character no-undo.\n",
+        "DEFINE VARIABLE        iCount AS INTEGER   NO-UNDO. /* c */\n\
+         DEFINE INPUT PARAMETER piId   AS INTEGER   NO-UNDO.\n\
+         DEFINE VARIABLE        cName  AS CHARACTER NO-UNDO.\n",
+        &[],
+    );
+}
+
+#[test]
+fn function_definitions() {
+    check(
+        "function getName returns character (input piId as integer, output pcName as character):\n\
+         \x20 return \"x\".\n\
+         end.\n",
+        "FUNCTION getName RETURNS CHARACTER (INPUT piId AS INTEGER, OUTPUT pcName AS CHARACTER):\n\
+         \tRETURN \"x\".\n\
+         END FUNCTION.\n",
+        &[],
+    );
+}
+
+#[test]
+fn function_end_comment_and_blank_lines() {
+    check(
+        "iA = 1.\n\
+         function getName returns character (input piId as integer):\n\
+         \x20 return \"x\".\n\
+         end.\n\
+         iB = 2.\n",
+        "iA = 1.\n\
+         \n\
+         FUNCTION getName RETURNS CHARACTER (INPUT piId AS INTEGER):\n\
+         \tRETURN \"x\".\n\
+         END FUNCTION. /* getName */\n\
+         \n\
+         iB = 2.\n",
+        &["--procedure-end-comment"],
+    );
+}
+
+#[test]
+fn class_members() {
+    check(
+        "class a.b.C inherits X implements Y final:\n\
+         \x20 define public property Name as character no-undo get. set.\n\
+         \x20 method public void Foo():\n\
+         \x20 end method.\n\
+         \x20 constructor public C():\n\
+         \x20 end constructor.\n\
+         end class.\n",
+        "CLASS a.b.C INHERITS X IMPLEMENTS Y FINAL:\n\
+         \tDEFINE PUBLIC PROPERTY Name AS CHARACTER NO-UNDO GET. SET.\n\
+         \n\
+         \tMETHOD PUBLIC VOID Foo():\n\
+         \tEND METHOD.\n\
+         \n\
+         \tCONSTRUCTOR PUBLIC C():\n\
+         \tEND CONSTRUCTOR.\n\
+         END CLASS.\n",
+        &[],
+    );
+}
+
+#[test]
+fn using_statements_sorted() {
+    check(
+        "using Progress.Lang.*.\n\
+         using OpenEdge.Net.HTTP.* from propath.\n\
+         using Progress.Lang.*.\n",
+        "USING OpenEdge.Net.HTTP.* FROM PROPATH.\n\
+         USING Progress.Lang.*.\n",
+        &["--sort-using"],
+    );
+}
+
+#[test]
+fn using_after_a_header_comment() {
+    check(
+        "/* Customer.cls */\n\
+         using Progress.Lang.*.\n\
+         using Acme.Util.Strings.\n",
+        "/* Customer.cls */\n\
+         USING Acme.Util.Strings.\n\
+         USING Progress.Lang.*.\n",
+        &["--sort-using"],
+    );
+}
+
+#[test]
+fn catch_and_finally_blocks() {
+    check(
+        "block-level on error undo, throw.\n\
+         do on error undo, throw:\n\
+         \x20 run x.p.\n\
+         \x20 catch e as Progress.Lang.Error:\n\
+         \x20   message e:GetMessage(1).\n\
+         \x20 end catch.\n\
+         \x20 finally:\n\
+         \x20   message \"done\".\n\
+         \x20 end finally.\n\
+         end.\n",
+        "BLOCK-LEVEL ON ERROR UNDO, THROW.\n\
+         DO ON ERROR UNDO, THROW:\n\
+         \tRUN x.p.\n\
+         \tCATCH e AS Progress.Lang.Error:\n\
+         \t\tMESSAGE e:GetMessage(1).\n\
+         \tEND CATCH.\n\
+         \tFINALLY:\n\
+         \t\tMESSAGE \"done\".\n\
+         \tEND FINALLY.\n\
+         END.\n",
+        &[],
+    );
+}
+
+#[test]
+fn if_else_chain_kept() {
+    check(
+        "if a = 1 then b = 2.\n\
+         else if a = 2 then\n\
+         \x20 b = 3.\n\
+         else b = 4.\n",
+        "IF a EQ 1 THEN b = 2.\n\
+         ELSE IF a EQ 2 THEN\n\
+         \tb = 3.\n\
+         ELSE b = 4.\n",
+        &[],
+    );
+}
+
+#[test]
+fn then_on_next_line() {
+    check(
+        "if a = 1 then b = 2.\n",
+        "IF a EQ 1\n\
+         \tTHEN b = 2.\n",
+        &["--then-placement", "next-line"],
+    );
+}
+
+#[test]
+fn single_branch_on_same_line() {
+    check(
+        "if a = 1 then\n\
+         \x20 b = 2.\n\
+         else\n\
+         \x20 b = 3.\n",
+        "IF a EQ 1 THEN b = 2.\n\
+         ELSE b = 3.\n",
+        &["--if-branch-placement", "same-line"],
+    );
+}
+
+#[test]
+fn else_chain_with_branches_on_next_line() {
+    check(
+        "if a = 1 then iA = 1. else if a = 2 then iA = 2. else iA = 3.\n",
+        "IF a EQ 1 THEN\n\
+         \tiA = 1.\n\
+         ELSE IF a EQ 2 THEN\n\
+         \tiA = 2.\n\
+         ELSE\n\
+         \tiA = 3.\n",
+        &["--if-branch-placement", "next-line"],
+    );
+}
+
+#[test]
+fn nested_if_on_its_own_line() {
+    check(
+        "if a = 1 then\n\
+         \x20 iA = 1.\n\
+         else if a = 2 then\n\
+         \x20 iA = 2.\n\
+         else\n\
+         \x20 iA = 3.\n",
+        "IF a EQ 1 THEN\n\
+         \tiA = 1.\n\
+         ELSE\n\
+         \tIF a EQ 2 THEN\n\
+         \t\tiA = 2.\n\
+         \tELSE\n\
+         \t\tiA = 3.\n",
+        &["--else-if-placement", "next-line"],
+    );
+}
+
+#[test]
+fn outer_else_after_a_nested_if() {
+    check(
+        "if lA then\n\
+         \x20 if lB then iC = 1.\n\
+         \x20 else if lD then iC = 2.\n\
+         \x20 else iC = 4.\n\
+         else iC = 3.\n\
+         if lA then\n\
+         \x20 if lB then iC = 1.\n\
+         \x20 else do:\n\
+         \x20   iC = 2.\n\
+         \x20 end.\n\
+         else iC = 3.\n\
+         iD = 1.\n",
+        "IF lA THEN\n\
+         \tIF lB THEN iC = 1.\n\
+         \tELSE IF lD THEN iC = 2.\n\
+         \tELSE iC = 4.\n\
+         ELSE iC = 3.\n\
+         IF lA THEN\n\
+         \tIF lB THEN iC = 1.\n\
+         \tELSE DO:\n\
+         \t\tiC = 2.\n\
+         \tEND.\n\
+         ELSE iC = 3.\n\
+         iD = 1.\n",
+        &[],
+    );
+}
+
+#[test]
+fn comment_opening_the_file_before_a_statement() {
+    check(
+        "/* Purpose: totals */\n\
+         iA = 1.\n\
+         if lA then iB = 2.\n",
+        "/* Purpose: totals */\n\
+         iA = 1.\n\
+         IF lA THEN iB = 2.\n",
+        &[],
+    );
+}
+
+#[test]
+fn case_statement() {
+    check(
+        "case cCode:\n\
+         when \"A\" then iY = 1.\n\
+         when \"B\" or when \"C\" then do:\n\
+         iY = 2.\n\
+         end.\n\
+         otherwise iY = 3.\n\
+         end.\n",
+        "CASE cCode:\n\
+         \tWHEN \"A\" THEN\n\
+         \t\tiY = 1.\n\
+         \tWHEN \"B\" OR WHEN \"C\" THEN DO:\n\
+         \t\tiY = 2.\n\
+         \tEND.\n\
+         \tOTHERWISE\n\
+         \t\tiY = 3.\n\
+         END CASE.\n",
+        &[],
+    );
+}
+
+#[test]
+fn record_phrases_one_buffer_per_line() {
+    check(
+        "for each Customer no-lock where Customer.Balance > 0, each Order of Customer no-lock, first SalesRep where SalesRep.Rep = Customer.Rep no-lock by Customer.Name:\n\
+         end.\n\
+         find first Customer where Customer.CustNum = 1 exclusive-lock no-error.\n",
+        "FOR EACH Customer WHERE Customer.Balance GT 0 NO-LOCK,\n\
+         \tEACH Order OF Customer NO-LOCK,\n\
+         \tFIRST SalesRep WHERE SalesRep.Rep EQ Customer.Rep NO-LOCK\n\
+         \tBY Customer.Name:\n\
+         END.\n\
+         FIND FIRST Customer WHERE Customer.CustNum EQ 1 EXCLUSIVE-LOCK NO-ERROR.\n",
+        &[],
+    );
+}
+
+#[test]
+fn record_phrase_comments_stay_in_place() {
+    check(
+        "for each Customer /* all */ no-lock where a = 1, /* first */\n\
+         \x20 each Order no-lock /* o */\n\
+         \x20 by Order.Num: /* sorted */\n\
+         end.\n",
+        "FOR EACH Customer /* all */ WHERE a EQ 1 NO-LOCK, /* first */\n\
+         \tEACH Order NO-LOCK /* o */\n\
+         \tBY Order.Num: /* sorted */\n\
+         END.\n",
+        &[],
+    );
+}
+
+#[test]
+fn record_phrase_comment_keeps_its_line_break() {
+    check(
+        "for each Customer no-lock /* c */\n\
+         \x20 by Customer.Name:\n\
+         end.\n\
+         for each Customer where a = 1 /* w */\n\
+         \x20 no-lock:\n\
+         end.\n",
+        "FOR EACH Customer NO-LOCK /* c */\n\
+         \tBY Customer.Name:\n\
+         END.\n\
+         FOR EACH Customer WHERE a EQ 1 /* w */\n\
+         \tNO-LOCK:\n\
+         END.\n",
+        &[],
+    );
+}
+
+#[test]
+fn fragment_phrase_trailing_comment() {
+    check(
+        "no-lock where a = 1 /* c */\n",
+        "WHERE a EQ 1 NO-LOCK /* c */\n",
+        &["--fragment"],
+    );
+}
+
+#[test]
+fn conditions_split_one_per_line() {
+    check(
+        "for each Customer where Customer.Balance > 100 and Customer.Name begins \"a\" or Customer.City = \"x\" no-lock:\n\
+         end.\n\
+         if lA and lB or lC then iA = 1.\n",
+        "FOR EACH Customer\n\
+         \tWHERE Customer.Balance GT 100\n\
+         \t  AND Customer.Name BEGINS \"a\"\n\
+         \t   OR Customer.City EQ \"x\" NO-LOCK:\n\
+         END.\n\
+         IF lA\n\
+         \tAND lB\n\
+         \t OR LC THEN iA = 1.\n",
+        &["--split-conditions"],
+    );
+}
+
+#[test]
+fn split_if_conditions_indented_in_a_block() {
+    check(
+        "do:\n\
+         \x20 if lA and lB or lC then iA = 1.\n\
+         end.\n\
+         if lA and (lB or lC) then do:\n\
+         \x20 iA = 2.\n\
+         end.\n",
+        "DO:\n\
+         \tIF lA\n\
+         \t\tAND lB\n\
+         \t\t OR LC THEN iA = 1.\n\
+         END.\n\
+         IF lA\n\
+         \tAND (lB\n\
+         \t  OR lC) THEN DO:\n\
+         \tiA = 2.\n\
+         END.\n",
+        &["--split-conditions"],
+    );
+}
+
+#[test]
+fn nested_groups_split_and_calls_kept() {
+    check(
+        "for each Customer where a = 1 and (b = 2 or (c = 3 and d = 4)) and lookup(e, \"x,y\") > 0 no-lock:\n\
+         end.\n",
+        "FOR EACH Customer\n\
+         \tWHERE a EQ 1\n\
+         \t  AND (b EQ 2\n\
+         \t    OR (c EQ 3\n\
+         \t    AND d EQ 4))\n\
+         \t  AND LOOKUP(e, \"x,y\") GT 0 NO-LOCK:\n\
+         END.\n",
+        &["--split-conditions"],
+    );
+}
+
+#[test]
+fn group_straight_after_operator() {
+    check(
+        "for each Customer where iA > 100 and(lOk or lDone) no-lock:\n\
+         end.\n",
+        "FOR EACH Customer\n\
+         \tWHERE iA GT 100\n\
+         \t  AND (lOk\n\
+         \t    OR lDone) NO-LOCK:\n\
+         END.\n",
+        &["--split-conditions"],
+    );
+}
+
+#[test]
+fn run_statements() {
+    check(
+        "run proc.p (input cName, output iCount, input-output table ttFoo by-reference) no-error.\n\
+         run x in hProc.\n\
+         run value(cProg).\n",
+        "RUN proc.p (INPUT cName, OUTPUT iCount, INPUT-OUTPUT TABLE ttFoo BY-REFERENCE) NO-ERROR.\n\
+         RUN x IN hProc.\n\
+         RUN VALUE(cProg).\n",
+        &[],
+    );
+}
+
+#[test]
+fn parameters_wrapped_past_line_width() {
+    check(
+        "run proc.p (input cName, output iCount, input-output table ttFoo by-reference) no-error.\n\
+         run x in hProc.\n\
+         run value(cProg).\n\
+         function getName returns character (input piId as integer, output pcName as character):\n\
+         end.\n",
+        "RUN proc.p (\n\
+         \tINPUT        cName,\n\
+         \tOUTPUT       iCount,\n\
+         \tINPUT-OUTPUT TABLE ttFoo BY-REFERENCE) NO-ERROR.\n\
+         RUN x IN hProc.\n\
+         RUN VALUE(cProg).\n\
+         \n\
+         FUNCTION getName RETURNS CHARACTER (\n\
+         \tINPUT  piId   AS INTEGER,\n\
+         \tOUTPUT pcName AS CHARACTER):\n\
+         END FUNCTION.\n",
+        &["--line-width", "60"],
+    );
+}
+
+#[test]
+fn message_and_display_kept_when_they_fit() {
+    check(
+        "message \"x\" skip \"y\" view-as alert-box question buttons yes-no update lOk.\n\
+         display cust.Name cust.Balance format \">>>,>>9.99\" with frame f down.\n",
+        "MESSAGE \"x\" SKIP \"y\" VIEW-AS ALERT-BOX QUESTION BUTTONS YES-NO UPDATE lOk.\n\
+         DISPLAY cust.Name cust.Balance FORMAT \">>>,>>9.99\" WITH FRAME f DOWN.\n",
+        &[],
+    );
+}
+
+#[test]
+fn one_item_per_line() {
+    check(
+        "display cName iCount format \">>9\" with frame f.\n",
+        "DISPLAY\n\
+         \tcName\n\
+         \tiCount FORMAT \">>9\"\n\
+         \tWITH FRAME f.\n",
+        &["--one-item-per-line"],
+    );
+}
+
+#[test]
+fn items_wrapped_past_line_width() {
+    check(
+        "message \"first part\" \"second part\" \"third part\".\n",
+        "MESSAGE\n\
+         \t\"first part\"\n\
+         \t\"second part\"\n\
+         \t\"third part\".\n",
+        &["--line-width", "30"],
+    );
+}
+
+#[test]
+fn item_statement_comments_keep_their_line_break() {
+    check(
+        "message \"x\" /* m */\n\
+         \x20 \"y\".\n\
+         display cName /* first */\n\
+         \x20 iCount /* second */\n\
+         \x20 dTotal with frame f.\n\
+         message \"x\" view-as alert-box /* box */\n\
+         \x20 buttons yes-no update lOk.\n",
+        "MESSAGE\n\
+         \t\"x\" /* m */\n\
+         \t\"y\".\n\
+         DISPLAY\n\
+         \tcName  /* first */\n\
+         \tiCount /* second */\n\
+         \tdTotal\n\
+         \tWITH FRAME f.\n\
+         MESSAGE\n\
+         \t\"x\"\n\
+         \tVIEW-AS ALERT-BOX /* box */\n\
+         \tBUTTONS YES-NO UPDATE lOk.\n",
+        &[],
+    );
+}
+
+#[test]
+fn triggers_and_wait_for() {
+    check(
+        "on choose of btnOk in frame fMain do:\n\
+         \x20 run ok.\n\
+         end.\n\
+         on \"F1\":U anywhere persistent run help.p.\n\
+         wait-for close of this-procedure.\n",
+        "ON CHOOSE OF btnOk IN FRAME fMain DO:\n\
+         \tRUN ok.\n\
+         END.\n\
+         ON \"F1\":U ANYWHERE PERSISTENT RUN help.p.\n\
+         WAIT-FOR CLOSE OF this-procedure.\n",
+        &[],
+    );
+}
+
+#[test]
+fn split_event_lists() {
+    check(
+        "on choose, leave of btnOk do:\n\
+         end.\n",
+        "ON CHOOSE,\n\
+         \tLEAVE OF btnOk DO:\n\
+         END.\n",
+        &["--split-event-lists"],
+    );
+}
+
+#[test]
+fn block_headers_and_labels() {
+    check(
+        "blk-main: do transaction on error undo blk-main, leave blk-main:\n\
+         \x20 repeat i = 1 to 10 by 2 while lOk:\n\
+         \x20 end.\n\
+         end.\n",
+        "blk-main:\n\
+         DO TRANSACTION ON ERROR UNDO blk-main, LEAVE blk-main:\n\
+         \tREPEAT i = 1 TO 10 BY 2 WHILE lOk:\n\
+         \tEND.\n\
+         END.\n",
+        &[],
+    );
+}
+
+#[test]
+fn label_on_same_line_with_end_comment() {
+    check(
+        "outer-loop:\n\
+         repeat:\n\
+         end.\n",
+        "outer-loop: REPEAT:\n\
+         END. /* outer-loop */\n",
+        &["--label-placement", "same-line", "--label-end-comment"],
+    );
+}
+
+#[test]
+fn qualified_names_and_chained_members() {
+    check(
+        "oObj = new OpenEdge.Core.String(\"a\").\n\
+         cVal = oObj:GetX():GetY():Name.\n\
+         cName = db.Customer.Name.\n",
+        "oObj = NEW OpenEdge.Core.String(\"a\").\n\
+         cVal = oObj:GetX():GetY():Name.\n\
+         cName = db.Customer.Name.\n",
+        &[],
+    );
+}
+
+#[test]
+fn member_names_kept_as_written() {
+    check(
+        "oObj:Do().\n\
+         cVal = oObj:Name + SELF:Label.\n",
+        "oObj:Do().\n\
+         cVal = oObj:Name + SELF:Label.\n",
+        &[],
+    );
+}
+
+#[test]
+fn include_references() {
+    check(
+        "{inc/x.i &Table=Customer &Where=\"'WHERE a'\"}\n\
+         {foo.i \"a\" \"b\"}\n",
+        "{inc/x.i &Table=Customer &Where=\"'WHERE a'\"}\n\
+         {foo.i \"a\" \"b\"}\n",
+        &[],
+    );
+}
+
+#[test]
+fn include_arguments_per_line() {
+    check(
+        "{inc/x.i &Table=Customer &Where=\"a\"}\n",
+        "{inc/x.i &Table=Customer\n\
+         \x20        &Where=\"a\"}\n",
+        &["--include-args-per-line"],
+    );
+}
+
+#[test]
+fn fragment_field_list() {
+    check(
+        "field a as character\n\
+         field bLong as integer\n",
+        "\tFIELD a     AS CHARACTER\n\
+         \tFIELD bLong AS INTEGER\n",
+        &["--fragment", "--base-indent", "1"],
+    );
+}
+
+#[test]
+fn fragment_field_list_ending_in_a_period() {
+    check(
+        "field a as char\n\
+         field b as int\n\
+         index ix a.\n",
+        "\tFIELD a AS CHAR\n\
+         \tFIELD b AS INT\n\
+         \tINDEX ix a.\n",
+        &["--fragment", "--base-indent", "1"],
+    );
+}
+
+#[test]
+fn fragment_phrase() {
+    check(
+        "where Customer.CustNum = 1 no-lock\n",
+        "WHERE Customer.CustNum EQ 1 NO-LOCK\n",
+        &["--fragment"],
+    );
+}
+
+#[test]
+fn fragment_statement_list() {
+    check(
+        "iA = 1.\n\
+         if lOk then\n\
+         \x20 iB = 2.\n",
+        "\t\tiA = 1.\n\
+         \t\tIF lOk THEN\n\
+         \t\t\tiB = 2.\n",
+        &["--fragment", "--base-indent", "2"],
+    );
+}
+
+#[test]
+fn windows_1252_source_written_back_in_windows_1252() {
+    let output = format(b"cName = \"caf\xe9\".\n", &[]);
+    assert_eq!(output.stdout, b"cName = \"caf\xe9\".\n");
+}
+
+#[test]
+fn byte_order_mark_kept() {
+    let output = format(b"\xef\xbb\xbfcName = \"caf\xc3\xa9\".\n", &[]);
+    assert_eq!(output.stdout, b"\xef\xbb\xbfcName = \"caf\xc3\xa9\".\n");
+}
+
+#[test]
+fn source_not_in_the_given_encoding_is_refused() {
+    let output = format(b"cName = \"caf\xe9\".\n", &["--encoding", "utf-8"]);
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("input is not valid in its encoding"));
+}
+
+#[test]
+fn crlf_source_written_back_with_crlf() {
+    check(
+        "iA = 1.\r\n\
+         iB = 2.\r\n",
+        "iA = 1.\r\n\
+         iB = 2.\r\n",
+        &[],
+    );
+}
+
+#[test]
+fn end_of_line_option_overrides_the_source() {
+    check(
+        "iA = 1.\r\n\
+         iB = 2.\r\n",
+        "iA = 1.\n\
+         iB = 2.\n",
+        &["--end-of-line", "lf"],
+    );
+}
+
+#[test]
+fn crlf_source_keeps_line_endings_inside_strings() {
+    check(
+        "iA = 1. /* a */\r\n\
+         cName = \"line one\n\
+         line two\".\r\n\
+         if lOk then\r\n\
+         \x20 iB = 2.\r\n",
+        "iA = 1. /* a */\r\n\
+         cName = \"line one\n\
+         line two\".\r\n\
+         IF lOk THEN\r\n\
+         \tiB = 2.\r\n",
+        &[],
+    );
+}
+
+#[test]
+fn crlf_written_only_at_line_ends() {
+    check(
+        "iA = 1.\n\
+         cName = \"a\r\n\
+         b\".\n",
+        "iA = 1.\r\n\
+         cName = \"a\r\n\
+         b\".\r\n",
+        &["--end-of-line", "crlf"],
+    );
+}
+
+#[test]
+fn blank_lines_capped() {
+    check(
+        "iA = 1.\n\
+         \n\
+         \n\
+         \n\
+         \n\
+         iB = 2.\n",
+        "iA = 1.\n\
+         \n\
+         iB = 2.\n",
+        &["--max-blank-lines", "1"],
+    );
+}
+
+#[test]
+fn blank_lines_after_defines_and_at_block_edges() {
+    check(
+        "define variable i as integer no-undo.\n\
+         i = 1.\n\
+         do:\n\
+         \n\
+         \x20 i = 2.\n\
+         \n\
+         end.\n",
+        "DEFINE VARIABLE i AS INTEGER NO-UNDO.\n\
+         \n\
+         i = 1.\n\
+         DO:\n\
+         \ti = 2.\n\
+         END.\n",
+        &["--define-blank-lines", "1", "--block-edge-blank-lines", "0"],
+    );
+}
+
+#[test]
+fn trailing_whitespace_and_final_newline() {
+    check(
+        "iA = 1.   \n\
+         iB = 2.\n\
+         \n\
+         \n",
+        "iA = 1.\n\
+         iB = 2.\n",
+        &[],
+    );
+}
+
+#[test]
+fn leading_blank_lines_dropped() {
+    check(
+        "\n\
+         \n\
+         \n\
+         /* head */\n\
+         iA = 1.\n",
+        "/* head */\n\
+         iA = 1.\n",
+        &[],
+    );
+}
+
+#[test]
+fn trailing_comments_aligned() {
+    check(
+        "define variable i as integer no-undo. /* counter */\n\
+         define variable cLonger as character no-undo. /* name */\n\
+         iA = 1. /* a */\n\
+         iLonger = 22. /* b */\n",
+        "DEFINE VARIABLE i       AS INTEGER   NO-UNDO. /* counter */\n\
+         DEFINE VARIABLE cLonger AS CHARACTER NO-UNDO. /* name */\n\
+         iA = 1.                                       /* a */\n\
+         iLonger = 22.                                 /* b */\n",
+        &["--align-trailing-comments"],
+    );
+}
+
+#[test]
+fn trailing_comment_gap() {
+    check(
+        "iA = 1. /* a */\n",
+        "iA = 1.   /* a */\n",
+        &["--trailing-comment-gap", "3"],
+    );
+}
+
+#[test]
+fn comments_before_branches_and_assignments_kept() {
+    check(
+        "if a = 1 then /* cond */\n\
+         \x20 iA = 1.\n\
+         if a = 1 then /* same line */ iA = 1.\n\
+         if a = 1 then\n\
+         \x20 /* own line */\n\
+         \x20 iA = 1.\n\
+         else /* other */\n\
+         \x20 iA = 2.\n\
+         assign /* lead */\n\
+         \x20 iA = 1\n\
+         \x20 iB = 2.\n",
+        "IF a EQ 1 THEN /* cond */\n\
+         \tiA = 1.\n\
+         IF a EQ 1 THEN /* same line */ iA = 1.\n\
+         IF a EQ 1 THEN\n\
+         \t/* own line */\n\
+         \tiA = 1.\n\
+         ELSE /* other */\n\
+         \tiA = 2.\n\
+         ASSIGN /* lead */\n\
+         \tiA = 1\n\
+         \tiB = 2.\n",
+        &[],
+    );
+}